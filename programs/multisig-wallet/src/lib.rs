@@ -1,38 +1,111 @@
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar::instructions::load_instruction_at_checked;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
 declare_id!("MSigWa11etProgram11111111111111111111111111");
 
+const SECONDS_PER_DAY: i64 = 86400;
+const SECONDS_PER_MONTH: i64 = SECONDS_PER_DAY * 30;
+const MAX_OWNERS: usize = 100;
+const MAX_GUARDIANS: usize = 10;
+const SOCIAL_RECOVERY_TIMELOCK: i64 = SECONDS_PER_DAY * 7;
+const MAX_POLICY_TIERS: usize = 8;
+const MAX_PAUSE_REASON_LEN: usize = 64;
+const MAX_UNPAUSE_COOLDOWN: i64 = SECONDS_PER_DAY * 30;
+const MAX_LABEL_LEN: usize = 32;
+/// Owners sign this prefix followed by the transaction address and its
+/// little-endian `vote_nonce` to approve it off-chain.
+pub const CONFIRMATION_MESSAGE_PREFIX: &[u8] = b"multisig-wallet:confirm:";
+
+/// May create transactions.
+pub const PERMISSION_PROPOSE: u8 = 1 << 0;
+/// May confirm, reject, pause and vote to unpause; counts toward the
+/// threshold.
+pub const PERMISSION_VOTE: u8 = 1 << 1;
+/// May execute approved transactions.
+pub const PERMISSION_EXECUTE: u8 = 1 << 2;
+pub const PERMISSION_ALL: u8 = PERMISSION_PROPOSE | PERMISSION_VOTE | PERMISSION_EXECUTE;
+
 #[program]
 pub mod multisig_wallet {
     use super::*;
 
     pub fn initialize_wallet(
         ctx: Context<InitializeWallet>,
-        owners: Vec<Pubkey>,
-        threshold: u8,
+        owners: Vec<Member>,
+        threshold: u16,
         time_lock_duration: i64,
         bump: u8,
+        index: u64,
     ) -> Result<()> {
         require!(
-            owners.len() > 0 && owners.len() <= 50,
+            !owners.is_empty() && owners.len() <= MAX_OWNERS,
             ErrorCode::InvalidOwnersCount
         );
+        for (position, member) in owners.iter().enumerate() {
+            require!(
+                member.permissions != 0 && member.permissions & !PERMISSION_ALL == 0,
+                ErrorCode::InvalidPermissions
+            );
+            require!(member.weight > 0, ErrorCode::InvalidWeight);
+            require!(
+                !owners[..position]
+                    .iter()
+                    .any(|other| other.key == member.key),
+                ErrorCode::OwnerAlreadyExists
+            );
+        }
+
+        let wallet = &mut ctx.accounts.wallet;
+        wallet.owners = owners.clone();
+        require!(wallet.has_every_permission(), ErrorCode::InvalidPermissions);
         require!(
-            threshold > 0 && threshold <= owners.len() as u8,
+            threshold > 0 && threshold <= wallet.total_weight(),
             ErrorCode::InvalidThreshold
         );
 
-        let wallet = &mut ctx.accounts.wallet;
-        wallet.owners = owners.clone();
+        wallet.authority = ctx.accounts.authority.key();
+        wallet.index = index;
         wallet.threshold = threshold;
         wallet.time_lock_duration = time_lock_duration;
         wallet.nonce = 0;
         wallet.bump = bump;
         wallet.is_paused = false;
+        wallet.vault_bump =
+            Pubkey::find_program_address(&[b"vault", wallet.key().as_ref()], ctx.program_id).1;
+        wallet.guardians = Vec::new();
+        wallet.recovery_count = 0;
+        wallet.proposal_expiry = None;
+        wallet.paused_by = None;
+        wallet.paused_at = 0;
+        wallet.pause_reason = String::new();
+        wallet.unpause_votes = Vec::new();
+        wallet.unpause_quorum = 0;
+        wallet.unpause_cooldown = 0;
+        wallet.stale_transaction_index = 0;
+        wallet.standing_order_count = 0;
+        wallet.vesting_stream_count = 0;
+        wallet.destination_policy = DestinationPolicy::Open;
+        wallet.address_book_delay = 0;
+        wallet.allowance_count = 0;
+        wallet.amount_policy_count = 0;
+        wallet.spending_limit_count = 0;
 
         emit!(WalletInitialized {
             wallet: wallet.key(),
+            authority: wallet.authority,
+            index,
             owners,
             threshold,
             time_lock_duration,
@@ -45,32 +118,62 @@ pub mod multisig_wallet {
         ctx: Context<CreateTransaction>,
         destination: Pubkey,
         amount: u64,
-        data: Vec<u8>,
+        instruction: Option<TransactionInstruction>,
         token_mint: Option<Pubkey>,
+        expires_at: Option<i64>,
     ) -> Result<()> {
-        let wallet = &ctx.accounts.wallet;
+        let wallet = &mut ctx.accounts.wallet;
         require!(!wallet.is_paused, ErrorCode::WalletPaused);
         require!(
-            wallet.owners.contains(&ctx.accounts.proposer.key()),
-            ErrorCode::NotOwner
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
         );
+        if let Some(ix) = &instruction {
+            require!(ix.program_id != crate::ID, ErrorCode::InvalidInstruction);
+        }
 
         let transaction = &mut ctx.accounts.transaction;
-        transaction.wallet = wallet.key();
+        init_transaction(
+            transaction,
+            wallet,
+            ctx.accounts.proposer.key(),
+            *ctx.bumps.get("transaction").unwrap(),
+            expires_at,
+        )?;
         transaction.destination = destination;
         transaction.amount = amount;
-        transaction.data = data;
+        transaction.instruction = instruction;
         transaction.token_mint = token_mint;
-        transaction.proposer = ctx.accounts.proposer.key();
-        transaction.is_executed = false;
-        transaction.confirmations = vec![ctx.accounts.proposer.key()];
-        transaction.created_at = Clock::get()?.unix_timestamp;
-        transaction.requires_all_confirmations = false;
+        transaction.config_action = None;
+        transaction.actions = Vec::new();
+
+        // Check spending limits
+        let spending_limits = load_spending_limits(
+            wallet.key(),
+            &transaction_spends(transaction),
+            &[ctx.accounts.spending_limit.to_account_info()],
+            transaction.created_at,
+        )?;
+        apply_spending_limits(transaction, wallet, &spending_limits);
+
+        apply_amount_policies(
+            transaction,
+            wallet,
+            &[ctx.accounts.amount_policy.to_account_info()],
+        )?;
+        let created_at = transaction.created_at;
+        apply_destination_policy(
+            wallet,
+            transaction,
+            &[ctx.accounts.address_book_entry.to_account_info()],
+            created_at,
+        )?;
 
         emit!(TransactionCreated {
             transaction: transaction.key(),
             wallet: wallet.key(),
             proposer: ctx.accounts.proposer.key(),
+            index: transaction.index,
             destination,
             amount,
             token_mint,
@@ -79,171 +182,3960 @@ pub mod multisig_wallet {
         Ok(())
     }
 
+    pub fn create_batch_transaction<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateBatchTransaction<'info>>,
+        actions: Vec<TransactionAction>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
+        );
+        require!(!actions.is_empty(), ErrorCode::EmptyBatch);
+        for action in &actions {
+            if let TransactionAction::Instruction(ix) = action {
+                require!(ix.program_id != crate::ID, ErrorCode::InvalidInstruction);
+            }
+        }
+
+        let transaction = &mut ctx.accounts.transaction;
+        init_transaction(
+            transaction,
+            wallet,
+            ctx.accounts.proposer.key(),
+            *ctx.bumps.get("transaction").unwrap(),
+            expires_at,
+        )?;
+        transaction.destination = wallet.key();
+        transaction.amount = 0;
+        transaction.instruction = None;
+        transaction.token_mint = None;
+        transaction.config_action = None;
+        transaction.actions = actions;
+
+        // Check spending limits; the limit and amount policy PDAs of every
+        // mint the batch spends are passed in the remaining accounts.
+        let spending_limits = load_spending_limits(
+            wallet.key(),
+            &transaction_spends(transaction),
+            ctx.remaining_accounts,
+            transaction.created_at,
+        )?;
+        apply_spending_limits(transaction, wallet, &spending_limits);
+
+        apply_amount_policies(transaction, wallet, ctx.remaining_accounts)?;
+        let created_at = transaction.created_at;
+        apply_destination_policy(wallet, transaction, ctx.remaining_accounts, created_at)?;
+
+        emit!(BatchTransactionCreated {
+            transaction: transaction.key(),
+            wallet: wallet.key(),
+            proposer: ctx.accounts.proposer.key(),
+            index: transaction.index,
+            action_count: transaction.actions.len() as u16,
+        });
+
+        Ok(())
+    }
+
     pub fn confirm_transaction(ctx: Context<ConfirmTransaction>) -> Result<()> {
         let wallet = &ctx.accounts.wallet;
         let transaction = &mut ctx.accounts.transaction;
 
-        require!(!wallet.is_paused, ErrorCode::WalletPaused);
         require!(
-            wallet.owners.contains(&ctx.accounts.owner.key()),
-            ErrorCode::NotOwner
+            !wallet.is_paused || runs_while_paused(transaction),
+            ErrorCode::WalletPaused
+        );
+        require!(
+            wallet.has_permission(&ctx.accounts.owner.key(), PERMISSION_VOTE),
+            ErrorCode::MissingPermission
         );
         require!(!transaction.is_executed, ErrorCode::TransactionExecuted);
+        require!(!transaction.is_cancelled, ErrorCode::TransactionCancelled);
+        require!(
+            transaction.index >= wallet.stale_transaction_index,
+            ErrorCode::StaleTransaction
+        );
+        require!(
+            !is_expired(transaction, Clock::get()?.unix_timestamp),
+            ErrorCode::TransactionExpired
+        );
         require!(
-            !transaction.confirmations.contains(&ctx.accounts.owner.key()),
+            !transaction
+                .confirmations
+                .contains(&ctx.accounts.owner.key()),
             ErrorCode::AlreadyConfirmed
         );
 
+        transaction
+            .rejections
+            .retain(|&x| x != ctx.accounts.owner.key());
         transaction.confirmations.push(ctx.accounts.owner.key());
 
         emit!(TransactionConfirmed {
             transaction: transaction.key(),
             owner: ctx.accounts.owner.key(),
-            confirmations: transaction.confirmations.len() as u8,
+            confirmed_weight: confirmed_weight(wallet, transaction),
+        });
+
+        Ok(())
+    }
+
+    /// Confirms on behalf of every voting owner whose signature over the
+    /// transaction's confirmation message is verified by an Ed25519 program
+    /// instruction in the same Solana transaction.
+    pub fn confirm_transaction_with_signatures(
+        ctx: Context<ConfirmTransactionWithSignatures>,
+    ) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let transaction = &mut ctx.accounts.transaction;
+
+        require!(
+            !wallet.is_paused || runs_while_paused(transaction),
+            ErrorCode::WalletPaused
+        );
+        require!(!transaction.is_executed, ErrorCode::TransactionExecuted);
+        require!(!transaction.is_cancelled, ErrorCode::TransactionCancelled);
+        require!(
+            transaction.index >= wallet.stale_transaction_index,
+            ErrorCode::StaleTransaction
+        );
+        require!(
+            !is_expired(transaction, Clock::get()?.unix_timestamp),
+            ErrorCode::TransactionExpired
+        );
+
+        let message = confirmation_message(&transaction.key(), transaction.vote_nonce);
+        let signers = ed25519_signers(&ctx.accounts.instructions, &message)?;
+        require!(!signers.is_empty(), ErrorCode::MissingSignatures);
+
+        for owner in signers {
+            require!(
+                wallet.has_permission(&owner, PERMISSION_VOTE),
+                ErrorCode::MissingPermission
+            );
+            // A rejection can only be withdrawn by the owner on-chain.
+            require!(
+                !transaction.rejections.contains(&owner),
+                ErrorCode::AlreadyRejected
+            );
+            // Owners may have confirmed on-chain while signatures were collected.
+            if transaction.confirmations.contains(&owner) {
+                continue;
+            }
+
+            transaction.confirmations.push(owner);
+
+            emit!(TransactionConfirmed {
+                transaction: transaction.key(),
+                owner,
+                confirmed_weight: confirmed_weight(wallet, transaction),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn reject_transaction(ctx: Context<RejectTransaction>) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let transaction = &mut ctx.accounts.transaction;
+
+        // Rejecting stays available while the wallet is paused so owners can
+        // kill suspicious proposals during an incident.
+        require!(
+            wallet.has_permission(&ctx.accounts.owner.key(), PERMISSION_VOTE),
+            ErrorCode::MissingPermission
+        );
+        require!(!transaction.is_executed, ErrorCode::TransactionExecuted);
+        require!(!transaction.is_cancelled, ErrorCode::TransactionCancelled);
+        require!(
+            transaction.index >= wallet.stale_transaction_index,
+            ErrorCode::StaleTransaction
+        );
+        require!(
+            !transaction.rejections.contains(&ctx.accounts.owner.key()),
+            ErrorCode::AlreadyRejected
+        );
+
+        transaction
+            .confirmations
+            .retain(|&x| x != ctx.accounts.owner.key());
+        transaction.rejections.push(ctx.accounts.owner.key());
+        // Signatures collected before the rejection no longer verify.
+        transaction.vote_nonce = transaction.vote_nonce.wrapping_add(1);
+
+        emit!(TransactionRejected {
+            transaction: transaction.key(),
+            owner: ctx.accounts.owner.key(),
+            rejected_weight: rejected_weight(wallet, transaction),
+        });
+
+        if !can_reach_threshold(wallet, transaction) {
+            transaction.is_cancelled = true;
+
+            emit!(TransactionCancelled {
+                transaction: transaction.key(),
+                cancelled_by: ctx.accounts.owner.key(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
+        let transaction = &mut ctx.accounts.transaction;
+
+        require_keys_eq!(
+            ctx.accounts.proposer.key(),
+            transaction.proposer,
+            ErrorCode::NotProposer
+        );
+        require!(!transaction.is_executed, ErrorCode::TransactionExecuted);
+        require!(!transaction.is_cancelled, ErrorCode::TransactionCancelled);
+        require!(
+            transaction
+                .confirmations
+                .iter()
+                .all(|&x| x == transaction.proposer),
+            ErrorCode::TransactionAlreadyConfirmed
+        );
+
+        transaction.is_cancelled = true;
+
+        emit!(TransactionCancelled {
+            transaction: transaction.key(),
+            cancelled_by: ctx.accounts.proposer.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn close_transaction(ctx: Context<CloseTransaction>) -> Result<()> {
+        let transaction = &ctx.accounts.transaction;
+
+        require!(
+            is_closable(
+                &ctx.accounts.wallet,
+                transaction,
+                Clock::get()?.unix_timestamp,
+            ),
+            ErrorCode::TransactionNotClosable
+        );
+
+        emit!(TransactionClosed {
+            transaction: transaction.key(),
+            proposer: transaction.proposer,
         });
 
         Ok(())
     }
 
-    pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
+    /// Closes many finished proposals at once. Remaining accounts are
+    /// `(transaction, proposer)` pairs; rent goes back to each proposer.
+    /// Pairs already closed, or not closable yet, are skipped so racing
+    /// cranks don't fail each other.
+    pub fn close_transactions<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseTransactions<'info>>,
+    ) -> Result<()> {
+        let wallet = ctx.accounts.wallet.key();
+        let current_time = Clock::get()?.unix_timestamp;
+
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            pairs.remainder().is_empty(),
+            ErrorCode::UnpairedCloseAccount
+        );
+
+        for pair in pairs {
+            let (info, proposer) = (&pair[0], &pair[1]);
+            if info.owner != &crate::ID || info.data_is_empty() {
+                continue;
+            }
+            let transaction: Account<Transaction> = Account::try_from(info)?;
+            require_keys_eq!(
+                transaction.wallet,
+                wallet,
+                anchor_lang::error::ErrorCode::ConstraintHasOne
+            );
+            require_keys_eq!(
+                transaction.proposer,
+                proposer.key(),
+                anchor_lang::error::ErrorCode::ConstraintHasOne
+            );
+            if !is_closable(&ctx.accounts.wallet, &transaction, current_time) {
+                continue;
+            }
+
+            transaction.close(proposer.clone())?;
+
+            emit!(TransactionClosed {
+                transaction: info.key(),
+                proposer: proposer.key(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn execute_transaction<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransaction<'info>>,
+    ) -> Result<()> {
         let wallet = &ctx.accounts.wallet;
         let transaction = &mut ctx.accounts.transaction;
 
         require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            wallet.has_permission(&ctx.accounts.executor.key(), PERMISSION_EXECUTE),
+            ErrorCode::MissingPermission
+        );
         require!(!transaction.is_executed, ErrorCode::TransactionExecuted);
-        
-        let required_confirmations = if transaction.requires_all_confirmations {
-            wallet.owners.len()
-        } else {
-            wallet.threshold as usize
-        };
+        require!(!transaction.is_cancelled, ErrorCode::TransactionCancelled);
+        require!(
+            transaction.index >= wallet.stale_transaction_index,
+            ErrorCode::StaleTransaction
+        );
+        require!(
+            transaction.config_action.is_none(),
+            ErrorCode::InvalidTransactionType
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            !is_expired(transaction, current_time),
+            ErrorCode::TransactionExpired
+        );
+
+        // Limits may have been consumed since the transaction was created, so
+        // re-check them against the current windows. They are charged what
+        // actually leaves the vault, Token-2022 transfer fees included.
+        let mut limit_accounts = ctx.remaining_accounts.to_vec();
+        limit_accounts.push(ctx.accounts.spending_limit.to_account_info());
+        if let Some(mint) = &ctx.accounts.mint {
+            limit_accounts.push(mint.to_account_info());
+        }
+        let spending_limits = load_spending_limits(
+            wallet.key(),
+            &transaction_spends_with_fees(transaction, &limit_accounts)?,
+            &limit_accounts,
+            current_time,
+        )?;
+        apply_spending_limits(transaction, wallet, &spending_limits);
+
+        // Entries may have been removed, or the policy tightened, since the
+        // proposal was created.
+        let mut entry_accounts = ctx.remaining_accounts.to_vec();
+        entry_accounts.push(ctx.accounts.address_book_entry.to_account_info());
+        apply_destination_policy(wallet, transaction, &entry_accounts, current_time)?;
 
         require!(
-            transaction.confirmations.len() >= required_confirmations,
+            can_execute_transaction(wallet, transaction),
             ErrorCode::InsufficientConfirmations
         );
 
-        // Check time lock
-        let current_time = Clock::get()?.unix_timestamp;
+        // Check time lock; unanimous consent overrides it
+        let time_lock_overridden =
+            current_time < transaction.created_at + transaction.time_lock_duration;
         require!(
-            current_time >= transaction.created_at + wallet.time_lock_duration,
+            !time_lock_overridden || is_unanimous(wallet, transaction),
             ErrorCode::TimeLockNotExpired
         );
 
+        for (mut spending_limit, amount) in spending_limits {
+            update_spending_limit(&mut spending_limit, amount);
+            spending_limit.exit(&crate::ID)?;
+        }
+
         transaction.is_executed = true;
+        let destination = transaction.destination;
+        let amount = transaction.amount;
+        let token_mint = transaction.token_mint;
+        let instruction = transaction.instruction.clone();
+        let actions = transaction.actions.clone();
+
+        let wallet_key = wallet.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", wallet_key.as_ref(), &[wallet.vault_bump]];
+
+        if amount > 0 {
+            transfer_from_vault(
+                ctx.accounts,
+                destination,
+                amount,
+                token_mint,
+                ctx.remaining_accounts,
+                &[vault_seeds],
+            )?;
+        }
+
+        if let Some(ix) = &instruction {
+            execute_instruction(
+                ix,
+                &ctx.accounts.vault.to_account_info(),
+                ctx.remaining_accounts,
+                &[vault_seeds],
+            )?;
+        }
+
+        execute_actions(
+            ctx.accounts,
+            &actions,
+            ctx.remaining_accounts,
+            &[vault_seeds],
+        )?;
+
+        if time_lock_overridden {
+            emit!(TimeLockOverridden {
+                transaction: ctx.accounts.transaction.key(),
+                executor: ctx.accounts.executor.key(),
+            });
+        }
 
         emit!(TransactionExecuted {
-            transaction: transaction.key(),
+            transaction: ctx.accounts.transaction.key(),
             executor: ctx.accounts.executor.key(),
         });
 
         Ok(())
     }
-}
 
-// Account structures
-#[account]
-pub struct Wallet {
-    pub owners: Vec<Pubkey>,
-    pub threshold: u8,
-    pub time_lock_duration: i64,
-    pub nonce: u64,
-    pub bump: u8,
-    pub is_paused: bool,
-}
+    pub fn create_config_transaction(
+        ctx: Context<CreateConfigTransaction>,
+        action: ConfigAction,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        // Owners can still vote out a compromised pauser while paused.
+        require!(
+            !wallet.is_paused || action.runs_while_paused(),
+            ErrorCode::WalletPaused
+        );
+        require!(
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
+        );
 
-#[account]
-pub struct Transaction {
-    pub wallet: Pubkey,
-    pub destination: Pubkey,
-    pub amount: u64,
-    pub data: Vec<u8>,
-    pub token_mint: Option<Pubkey>,
-    pub proposer: Pubkey,
-    pub is_executed: bool,
-    pub confirmations: Vec<Pubkey>,
-    pub created_at: i64,
-    pub requires_all_confirmations: bool,
-}
+        let transaction = &mut ctx.accounts.transaction;
+        init_transaction(
+            transaction,
+            wallet,
+            ctx.accounts.proposer.key(),
+            *ctx.bumps.get("transaction").unwrap(),
+            expires_at,
+        )?;
+        transaction.destination = wallet.key();
+        transaction.amount = 0;
+        transaction.instruction = None;
+        transaction.token_mint = None;
+        transaction.config_action = Some(action.clone());
+        transaction.actions = Vec::new();
 
-// Context structures
-#[derive(Accounts)]
-#[instruction(owners: Vec<Pubkey>, threshold: u8, time_lock_duration: i64, bump: u8)]
-pub struct InitializeWallet<'info> {
-    #[account(
-        init,
-        seeds = [b"wallet", authority.key().as_ref()],
-        bump,
-        payer = authority,
-        space = 8 + 4 + 32 * 50 + 1 + 8 + 8 + 1 + 1
-    )]
-    pub wallet: Account<'info, Wallet>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        emit!(ConfigTransactionCreated {
+            transaction: transaction.key(),
+            wallet: wallet.key(),
+            proposer: ctx.accounts.proposer.key(),
+            index: transaction.index,
+            action,
+        });
 
-#[derive(Accounts)]
-pub struct CreateTransaction<'info> {
-    pub wallet: Account<'info, Wallet>,
-    #[account(
-        init,
-        payer = proposer,
-        space = 8 + 32 + 32 + 8 + 4 + 1024 + 33 + 32 + 1 + 4 + 32 * 50 + 8 + 1
-    )]
-    pub transaction: Account<'info, Transaction>,
-    #[account(mut)]
-    pub proposer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ConfirmTransaction<'info> {
-    pub wallet: Account<'info, Wallet>,
-    #[account(mut)]
-    pub transaction: Account<'info, Transaction>,
-    pub owner: Signer<'info>,
-}
+    pub fn execute_config_transaction<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteConfigTransaction<'info>>,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        let transaction = &mut ctx.accounts.transaction;
 
-#[derive(Accounts)]
-pub struct ExecuteTransaction<'info> {
-    pub wallet: Account<'info, Wallet>,
-    #[account(mut)]
-    pub transaction: Account<'info, Transaction>,
-    pub executor: Signer<'info>,
-}
+        require!(
+            !wallet.is_paused || runs_while_paused(transaction),
+            ErrorCode::WalletPaused
+        );
+        require!(
+            wallet.has_permission(&ctx.accounts.executor.key(), PERMISSION_EXECUTE),
+            ErrorCode::MissingPermission
+        );
+        require!(!transaction.is_executed, ErrorCode::TransactionExecuted);
+        require!(!transaction.is_cancelled, ErrorCode::TransactionCancelled);
+        require!(
+            transaction.index >= wallet.stale_transaction_index,
+            ErrorCode::StaleTransaction
+        );
+        require!(
+            can_execute_transaction(wallet, transaction),
+            ErrorCode::InsufficientConfirmations
+        );
 
-// Events
-#[event]
-pub struct WalletInitialized {
-    pub wallet: Pubkey,
-    pub owners: Vec<Pubkey>,
-    pub threshold: u8,
-    pub time_lock_duration: i64,
-}
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            !is_expired(transaction, current_time),
+            ErrorCode::TransactionExpired
+        );
 
-#[event]
-pub struct TransactionCreated {
-    pub transaction: Pubkey,
-    pub wallet: Pubkey,
-    pub proposer: Pubkey,
-    pub destination: Pubkey,
-    pub amount: u64,
-    pub token_mint: Option<Pubkey>,
-}
+        // Check time lock; unanimous consent overrides it
+        let time_lock_overridden =
+            current_time < transaction.created_at + transaction.time_lock_duration;
+        require!(
+            !time_lock_overridden || is_unanimous(wallet, transaction),
+            ErrorCode::TimeLockNotExpired
+        );
 
-#[event]
-pub struct TransactionConfirmed {
-    pub transaction: Pubkey,
-    pub owner: Pubkey,
-    pub confirmations: u8,
-}
+        let action = transaction
+            .config_action
+            .clone()
+            .ok_or(ErrorCode::InvalidTransactionType)?;
+        apply_config_action(
+            wallet,
+            &action,
+            ctx.remaining_accounts,
+            &ctx.accounts.executor,
+            &ctx.accounts.system_program,
+        )?;
+        resize_wallet(wallet, &ctx.accounts.executor, &ctx.accounts.system_program)?;
 
-#[event]
-pub struct TransactionExecuted {
-    pub transaction: Pubkey,
-    pub executor: Pubkey,
-}
+        transaction.is_executed = true;
+
+        if time_lock_overridden {
+            emit!(TimeLockOverridden {
+                transaction: transaction.key(),
+                executor: ctx.accounts.executor.key(),
+            });
+        }
+
+        emit!(TransactionExecuted {
+            transaction: transaction.key(),
+            executor: ctx.accounts.executor.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn initiate_social_recovery(
+        ctx: Context<InitiateSocialRecovery>,
+        old_owner: Pubkey,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        require!(
+            wallet.guardians.contains(&ctx.accounts.guardian.key()),
+            ErrorCode::NotGuardian
+        );
+        require!(wallet.is_owner(&old_owner), ErrorCode::OwnerNotFound);
+        require!(
+            !wallet.is_owner(&new_owner) && new_owner != Pubkey::default(),
+            ErrorCode::OwnerAlreadyExists
+        );
+
+        let recovery_id = wallet.recovery_count;
+        wallet.recovery_count += 1;
+
+        let recovery = &mut ctx.accounts.recovery;
+        recovery.wallet = wallet.key();
+        recovery.recovery_id = recovery_id;
+        recovery.old_owner = old_owner;
+        recovery.new_owner = new_owner;
+        recovery.confirmations = vec![ctx.accounts.guardian.key()];
+        recovery.is_executed = false;
+        recovery.is_cancelled = false;
+        recovery.initiated_at = Clock::get()?.unix_timestamp;
+        recovery.bump = *ctx.bumps.get("recovery").unwrap();
+
+        emit!(SocialRecoveryInitiated {
+            wallet: wallet.key(),
+            recovery: recovery.key(),
+            new_owner,
+            recovery_id,
+        });
+
+        Ok(())
+    }
+
+    pub fn confirm_social_recovery(ctx: Context<ConfirmSocialRecovery>) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let recovery = &mut ctx.accounts.recovery;
+
+        require!(
+            wallet.guardians.contains(&ctx.accounts.guardian.key()),
+            ErrorCode::NotGuardian
+        );
+        require!(!recovery.is_executed, ErrorCode::RecoveryExecuted);
+        require!(!recovery.is_cancelled, ErrorCode::RecoveryCancelled);
+        require!(
+            !recovery
+                .confirmations
+                .contains(&ctx.accounts.guardian.key()),
+            ErrorCode::AlreadyConfirmed
+        );
+
+        // Drop confirmations from guardians that have since been removed.
+        recovery
+            .confirmations
+            .retain(|guardian| wallet.guardians.contains(guardian));
+        recovery.confirmations.push(ctx.accounts.guardian.key());
+
+        Ok(())
+    }
+
+    pub fn execute_social_recovery(ctx: Context<ExecuteSocialRecovery>) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        let recovery = &mut ctx.accounts.recovery;
+
+        require!(!recovery.is_executed, ErrorCode::RecoveryExecuted);
+        require!(!recovery.is_cancelled, ErrorCode::RecoveryCancelled);
+
+        // Guardians removed since confirming no longer count toward the quorum.
+        let guardian_confirmations = recovery
+            .confirmations
+            .iter()
+            .filter(|guardian| wallet.guardians.contains(guardian))
+            .count();
+        require!(
+            guardian_confirmations > wallet.guardians.len() / 2,
+            ErrorCode::InsufficientConfirmations
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= recovery.initiated_at + SOCIAL_RECOVERY_TIMELOCK,
+            ErrorCode::TimeLockNotExpired
+        );
+
+        // The owner set may have changed while the recovery was pending.
+        require!(
+            !wallet.is_owner(&recovery.new_owner),
+            ErrorCode::OwnerAlreadyExists
+        );
+        let member = wallet
+            .owners
+            .iter_mut()
+            .find(|member| member.key == recovery.old_owner)
+            .ok_or(ErrorCode::OwnerNotFound)?;
+        member.key = recovery.new_owner;
+        wallet.invalidate_pending_transactions();
+
+        recovery.is_executed = true;
+
+        emit!(SocialRecoveryExecuted {
+            wallet: wallet.key(),
+            recovery: recovery.key(),
+            recovery_id: recovery.recovery_id,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_social_recovery(ctx: Context<CancelSocialRecovery>) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let recovery = &mut ctx.accounts.recovery;
+
+        // The key being replaced is presumed lost or compromised, so it can't
+        // veto its own recovery.
+        require!(
+            wallet.has_permission(&ctx.accounts.owner.key(), PERMISSION_VOTE)
+                && ctx.accounts.owner.key() != recovery.old_owner,
+            ErrorCode::MissingPermission
+        );
+        require!(!recovery.is_executed, ErrorCode::RecoveryExecuted);
+        require!(!recovery.is_cancelled, ErrorCode::RecoveryCancelled);
+
+        recovery.is_cancelled = true;
+
+        emit!(SocialRecoveryCancelled {
+            wallet: wallet.key(),
+            recovery: recovery.key(),
+            recovery_id: recovery.recovery_id,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Any voting owner or guardian can pause instantly. A key that keeps
+    /// re-pausing can be voted out with `RemoveOwner`, `SetPermissions` or
+    /// `RemoveGuardian`, which stay available while paused.
+    pub fn pause_wallet(ctx: Context<PauseWallet>, reason: String) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        let pauser = ctx.accounts.pauser.key();
+        require!(
+            wallet.has_permission(&pauser, PERMISSION_VOTE) || wallet.guardians.contains(&pauser),
+            ErrorCode::NotOwner
+        );
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            reason.len() <= MAX_PAUSE_REASON_LEN,
+            ErrorCode::PauseReasonTooLong
+        );
+
+        wallet.is_paused = true;
+        wallet.paused_by = Some(pauser);
+        wallet.paused_at = Clock::get()?.unix_timestamp;
+        wallet.pause_reason = reason.clone();
+        wallet.unpause_votes = Vec::new();
+
+        emit!(WalletPaused {
+            wallet: wallet.key(),
+            paused_by: pauser,
+            reason,
+        });
+
+        Ok(())
+    }
+
+    /// Records a vote to unpause. The wallet unpauses once the votes reach
+    /// the unpause quorum and the cool-down since pausing has passed;
+    /// voters can call again to finish it after the cool-down.
+    pub fn unpause_wallet(ctx: Context<UnpauseWallet>) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        let owner = ctx.accounts.owner.key();
+        require!(
+            wallet.has_permission(&owner, PERMISSION_VOTE),
+            ErrorCode::MissingPermission
+        );
+        require!(wallet.is_paused, ErrorCode::WalletNotPaused);
+
+        if !wallet.unpause_votes.contains(&owner) {
+            wallet.unpause_votes.push(owner);
+        }
+
+        let votes: u16 = wallet
+            .unpause_votes
+            .iter()
+            .map(|voter| wallet.weight_of(voter))
+            .sum();
+        emit!(UnpauseVoted {
+            wallet: wallet.key(),
+            owner,
+            votes,
+        });
+
+        let cooled_down = Clock::get()?.unix_timestamp
+            >= wallet.paused_at.saturating_add(wallet.unpause_cooldown);
+        if votes >= wallet.required_unpause_votes() && cooled_down {
+            wallet.is_paused = false;
+            wallet.paused_by = None;
+            wallet.pause_reason = String::new();
+            wallet.unpause_votes = Vec::new();
+
+            emit!(WalletUnpaused {
+                wallet: wallet.key(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Drafts a recurring payment. It pays nothing until a config
+    /// transaction with `ApproveStandingOrder` is executed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_standing_order(
+        ctx: Context<CreateStandingOrder>,
+        recipient: Pubkey,
+        token_mint: Option<Pubkey>,
+        amount: u64,
+        period: i64,
+        start_time: i64,
+        end_time: Option<i64>,
+        max_occurrences: Option<u32>,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
+        );
+        require!(
+            amount > 0
+                && period > 0
+                && end_time.map_or(true, |end_time| end_time >= start_time)
+                && max_occurrences.map_or(true, |max| max > 0),
+            ErrorCode::InvalidStandingOrder
+        );
+
+        let order_id = wallet.standing_order_count;
+        wallet.standing_order_count += 1;
+
+        let standing_order = &mut ctx.accounts.standing_order;
+        standing_order.wallet = wallet.key();
+        standing_order.order_id = order_id;
+        standing_order.proposer = ctx.accounts.proposer.key();
+        standing_order.recipient = recipient;
+        standing_order.token_mint = token_mint;
+        standing_order.amount = amount;
+        standing_order.period = period;
+        standing_order.start_time = start_time;
+        standing_order.end_time = end_time;
+        standing_order.max_occurrences = max_occurrences;
+        standing_order.occurrences_paid = 0;
+        standing_order.is_approved = false;
+        standing_order.is_cancelled = false;
+        standing_order.bump = *ctx.bumps.get("standing_order").unwrap();
+
+        emit!(StandingOrderCreated {
+            wallet: wallet.key(),
+            standing_order: standing_order.key(),
+            order_id,
+            recipient,
+            token_mint,
+            amount,
+            period,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the next installment of an approved standing order once it is
+    /// due. Anyone can crank it; missed installments are paid one call at a
+    /// time.
+    pub fn execute_standing_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteStandingOrder<'info>>,
+    ) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let standing_order = &mut ctx.accounts.standing_order;
+
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            standing_order.is_approved,
+            ErrorCode::StandingOrderNotApproved
+        );
+        require!(
+            !standing_order.is_cancelled,
+            ErrorCode::StandingOrderCancelled
+        );
+        require!(
+            !standing_order.is_finished(),
+            ErrorCode::StandingOrderFinished
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= standing_order.next_due_at(),
+            ErrorCode::InstallmentNotDue
+        );
+        check_payout_destination(
+            wallet,
+            standing_order.recipient,
+            &ctx.accounts.address_book_entry,
+            current_time,
+            false,
+        )?;
+
+        // Installments have no approval round to escalate to, so they must
+        // fit the spending limits outright.
+        let spent = match (standing_order.token_mint, &ctx.accounts.mint) {
+            (None, _) => standing_order.amount,
+            (Some(_), Some(mint)) => standing_order.amount.saturating_add(transfer_fee(
+                &mint.to_account_info(),
+                standing_order.amount,
+            )?),
+            (Some(_), None) => return err!(ErrorCode::MissingTransferAccount),
+        };
+        charge_spending_limits_strict(
+            wallet.key(),
+            standing_order.token_mint,
+            spent,
+            &ctx.accounts.spending_limit,
+            current_time,
+        )?;
+
+        standing_order.occurrences_paid += 1;
+        let occurrence = standing_order.occurrences_paid;
+        let recipient = standing_order.recipient;
+        let amount = standing_order.amount;
+        let token_mint = standing_order.token_mint;
+
+        let wallet_key = wallet.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", wallet_key.as_ref(), &[wallet.vault_bump]];
+
+        let accounts = &ctx.accounts;
+        pay_from_vault(
+            &PayoutAccounts {
+                vault: &accounts.vault,
+                recipient: accounts.recipient.as_ref().map(AsRef::as_ref),
+                vault_token_account: accounts.vault_token_account.as_ref(),
+                recipient_token_account: accounts.recipient_token_account.as_ref(),
+                mint: accounts.mint.as_ref(),
+                token_program: accounts.token_program.as_ref(),
+                system_program: &accounts.system_program,
+            },
+            recipient,
+            token_mint,
+            amount,
+            ctx.remaining_accounts,
+            &[vault_seeds],
+        )?;
+
+        emit!(StandingOrderExecuted {
+            standing_order: ctx.accounts.standing_order.key(),
+            occurrence,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Drafts a vesting stream. Nothing can be claimed until a config
+    /// transaction with `ApproveVestingStream` is executed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_vesting_stream(
+        ctx: Context<CreateVestingStream>,
+        beneficiary: Pubkey,
+        token_mint: Option<Pubkey>,
+        total_amount: u64,
+        start_time: i64,
+        cliff_time: i64,
+        end_time: i64,
+        is_revocable: bool,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
+        );
+        require!(
+            total_amount > 0
+                && start_time <= cliff_time
+                && cliff_time <= end_time
+                && start_time < end_time,
+            ErrorCode::InvalidVestingStream
+        );
+
+        let stream_id = wallet.vesting_stream_count;
+        wallet.vesting_stream_count += 1;
+
+        let vesting_stream = &mut ctx.accounts.vesting_stream;
+        vesting_stream.wallet = wallet.key();
+        vesting_stream.stream_id = stream_id;
+        vesting_stream.proposer = ctx.accounts.proposer.key();
+        vesting_stream.beneficiary = beneficiary;
+        vesting_stream.token_mint = token_mint;
+        vesting_stream.total_amount = total_amount;
+        vesting_stream.start_time = start_time;
+        vesting_stream.cliff_time = cliff_time;
+        vesting_stream.end_time = end_time;
+        vesting_stream.claimed_amount = 0;
+        vesting_stream.is_revocable = is_revocable;
+        vesting_stream.is_approved = false;
+        vesting_stream.revoked_at = None;
+        vesting_stream.bump = *ctx.bumps.get("vesting_stream").unwrap();
+
+        emit!(VestingStreamCreated {
+            wallet: wallet.key(),
+            vesting_stream: vesting_stream.key(),
+            stream_id,
+            beneficiary,
+            token_mint,
+            total_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the beneficiary everything vested and not yet claimed.
+    pub fn claim_vesting<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimVesting<'info>>,
+    ) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let vesting_stream = &mut ctx.accounts.vesting_stream;
+
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            vesting_stream.is_approved,
+            ErrorCode::VestingStreamNotApproved
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        check_payout_destination(
+            wallet,
+            vesting_stream.beneficiary,
+            &ctx.accounts.address_book_entry,
+            current_time,
+            false,
+        )?;
+
+        let vested = vesting_stream.vested_amount(current_time);
+        let amount = vested.saturating_sub(vesting_stream.claimed_amount);
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        vesting_stream.claimed_amount = vested;
+        let token_mint = vesting_stream.token_mint;
+        let beneficiary = vesting_stream.beneficiary;
+
+        let wallet_key = wallet.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", wallet_key.as_ref(), &[wallet.vault_bump]];
+
+        let accounts = &ctx.accounts;
+        pay_from_vault(
+            &PayoutAccounts {
+                vault: &accounts.vault,
+                recipient: Some(accounts.beneficiary.as_ref()),
+                vault_token_account: accounts.vault_token_account.as_ref(),
+                recipient_token_account: accounts.beneficiary_token_account.as_ref(),
+                mint: accounts.mint.as_ref(),
+                token_program: accounts.token_program.as_ref(),
+                system_program: &accounts.system_program,
+            },
+            beneficiary,
+            token_mint,
+            amount,
+            ctx.remaining_accounts,
+            &[vault_seeds],
+        )?;
+
+        emit!(VestingClaimed {
+            vesting_stream: ctx.accounts.vesting_stream.key(),
+            beneficiary,
+            amount,
+            claimed_amount: vested,
+        });
+
+        emit!(TransactionExecuted {
+            transaction: ctx.accounts.vesting_stream.key(),
+            executor: beneficiary,
+        });
+
+        Ok(())
+    }
+
+    /// Drafts an address book entry. It takes effect `address_book_delay`
+    /// seconds after a config transaction with `ApproveAddressBookEntry` is
+    /// executed.
+    pub fn add_address_book_entry(
+        ctx: Context<AddAddressBookEntry>,
+        address: Pubkey,
+        label: String,
+    ) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        require!(
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
+        );
+        require!(label.len() <= MAX_LABEL_LEN, ErrorCode::LabelTooLong);
+
+        let entry = &mut ctx.accounts.address_book_entry;
+        // Re-drafting a listed entry would quietly delist it.
+        require!(entry.active_at.is_none(), ErrorCode::AddressAlreadyListed);
+
+        entry.wallet = wallet.key();
+        entry.address = address;
+        entry.label = label.clone();
+        entry.bump = *ctx.bumps.get("address_book_entry").unwrap();
+
+        emit!(AddressBookEntryAdded {
+            wallet: wallet.key(),
+            entry: entry.key(),
+            address,
+            label,
+        });
+
+        Ok(())
+    }
+
+    /// Drafts a spending allowance for `grantee`. It can be spent only after
+    /// a config transaction with `ApproveAllowance` is executed.
+    pub fn create_allowance(
+        ctx: Context<CreateAllowance>,
+        grantee: Pubkey,
+        token_mint: Option<Pubkey>,
+        amount: u64,
+        period: i64,
+        allowlisted_only: bool,
+    ) -> Result<()> {
+        let wallet = &mut ctx.accounts.wallet;
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(
+            wallet.has_permission(&ctx.accounts.proposer.key(), PERMISSION_PROPOSE),
+            ErrorCode::MissingPermission
+        );
+        require!(amount > 0 && period > 0, ErrorCode::InvalidAllowance);
+
+        let allowance_id = wallet.allowance_count;
+        wallet.allowance_count += 1;
+
+        let allowance = &mut ctx.accounts.allowance;
+        allowance.wallet = wallet.key();
+        allowance.allowance_id = allowance_id;
+        allowance.proposer = ctx.accounts.proposer.key();
+        allowance.grantee = grantee;
+        allowance.token_mint = token_mint;
+        allowance.amount = amount;
+        allowance.period = period;
+        allowance.period_start = 0;
+        allowance.spent_in_period = 0;
+        allowance.allowlisted_only = allowlisted_only;
+        allowance.is_approved = false;
+        allowance.is_revoked = false;
+        allowance.bump = *ctx.bumps.get("allowance").unwrap();
+
+        emit!(AllowanceCreated {
+            wallet: wallet.key(),
+            allowance: allowance.key(),
+            allowance_id,
+            grantee,
+            token_mint,
+            amount,
+            period,
+        });
+
+        Ok(())
+    }
+
+    /// Pays `amount` to `destination` under an approved allowance, signed by
+    /// the grantee alone.
+    pub fn spend_allowance<'info>(
+        ctx: Context<'_, '_, '_, 'info, SpendAllowance<'info>>,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        let wallet = &ctx.accounts.wallet;
+        let allowance = &mut ctx.accounts.allowance;
+
+        require!(!wallet.is_paused, ErrorCode::WalletPaused);
+        require!(allowance.is_approved, ErrorCode::AllowanceNotApproved);
+        require!(!allowance.is_revoked, ErrorCode::AllowanceRevoked);
+        require!(amount > 0, ErrorCode::InvalidAllowance);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        check_payout_destination(
+            wallet,
+            destination,
+            &ctx.accounts.address_book_entry,
+            current_time,
+            allowance.allowlisted_only,
+        )?;
+
+        let spent = match (allowance.token_mint, &ctx.accounts.mint) {
+            (None, _) => amount,
+            (Some(_), Some(mint)) => {
+                amount.saturating_add(transfer_fee(&mint.to_account_info(), amount)?)
+            }
+            (Some(_), None) => return err!(ErrorCode::MissingTransferAccount),
+        };
+        allowance.roll_period(current_time);
+        allowance.spent_in_period = allowance
+            .spent_in_period
+            .checked_add(spent)
+            .filter(|total| *total <= allowance.amount)
+            .ok_or(ErrorCode::AllowanceExceeded)?;
+
+        charge_spending_limits_strict(
+            wallet.key(),
+            allowance.token_mint,
+            spent,
+            &ctx.accounts.spending_limit,
+            current_time,
+        )?;
+
+        let spent_in_period = allowance.spent_in_period;
+        let token_mint = allowance.token_mint;
+
+        let wallet_key = wallet.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", wallet_key.as_ref(), &[wallet.vault_bump]];
+
+        let accounts = &ctx.accounts;
+        pay_from_vault(
+            &PayoutAccounts {
+                vault: &accounts.vault,
+                recipient: accounts.recipient.as_ref().map(AsRef::as_ref),
+                vault_token_account: accounts.vault_token_account.as_ref(),
+                recipient_token_account: accounts.recipient_token_account.as_ref(),
+                mint: accounts.mint.as_ref(),
+                token_program: accounts.token_program.as_ref(),
+                system_program: &accounts.system_program,
+            },
+            destination,
+            token_mint,
+            amount,
+            ctx.remaining_accounts,
+            &[vault_seeds],
+        )?;
+
+        emit!(AllowanceSpent {
+            allowance: ctx.accounts.allowance.key(),
+            grantee: ctx.accounts.grantee.key(),
+            destination,
+            amount,
+            spent_in_period,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper functions
+fn required_weight(wallet: &Wallet, transaction: &Transaction) -> u16 {
+    if transaction.requires_all_confirmations {
+        wallet.total_weight()
+    } else {
+        transaction.threshold.min(wallet.total_weight())
+    }
+}
+
+/// Combined weight of the confirmations that still belong to voting members.
+fn confirmed_weight(wallet: &Wallet, transaction: &Transaction) -> u16 {
+    transaction
+        .confirmations
+        .iter()
+        .map(|owner| wallet.weight_of(owner))
+        .sum()
+}
+
+fn can_execute_transaction(wallet: &Wallet, transaction: &Transaction) -> bool {
+    confirmed_weight(wallet, transaction) >= required_weight(wallet, transaction)
+}
+
+/// Whether every current voting member has confirmed.
+fn is_unanimous(wallet: &Wallet, transaction: &Transaction) -> bool {
+    wallet
+        .owners
+        .iter()
+        .filter(|member| member.permissions & PERMISSION_VOTE != 0)
+        .all(|member| transaction.confirmations.contains(&member.key))
+}
+
+/// Canonical message an owner signs to confirm `transaction` off-chain.
+fn confirmation_message(transaction: &Pubkey, vote_nonce: u32) -> Vec<u8> {
+    [
+        CONFIRMATION_MESSAGE_PREFIX,
+        transaction.as_ref(),
+        &vote_nonce.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Public keys whose signatures over `message` are checked by Ed25519
+/// program instructions in the current transaction.
+fn ed25519_signers(instructions: &AccountInfo, message: &[u8]) -> Result<Vec<Pubkey>> {
+    // Signature count and padding, then 14 bytes of offsets per signature.
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+
+    let mut signers = Vec::new();
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        index += 1;
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+
+        let data = &ix.data;
+        let count = *data.first().ok_or(ErrorCode::InvalidSignatureInstruction)? as usize;
+        for i in 0..count {
+            let start = HEADER_LEN + i * OFFSETS_LEN;
+            let offsets = data
+                .get(start..start + OFFSETS_LEN)
+                .ok_or(ErrorCode::InvalidSignatureInstruction)?;
+            let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+            // The signature, key and message must all live in the Ed25519
+            // instruction itself, or the precompile checked other bytes.
+            require!(
+                read(2) == u16::MAX && read(6) == u16::MAX && read(12) == u16::MAX,
+                ErrorCode::InvalidSignatureInstruction
+            );
+
+            let key_start = read(4) as usize;
+            let message_start = read(8) as usize;
+            let message_end = message_start + read(10) as usize;
+            let key: [u8; 32] = data
+                .get(key_start..key_start + 32)
+                .and_then(|key| key.try_into().ok())
+                .ok_or(ErrorCode::InvalidSignatureInstruction)?;
+
+            if data.get(message_start..message_end) == Some(message) {
+                signers.push(Pubkey::new_from_array(key));
+            }
+        }
+    }
+
+    Ok(signers)
+}
+
+/// Combined weight of the rejections that still belong to voting members.
+fn rejected_weight(wallet: &Wallet, transaction: &Transaction) -> u16 {
+    transaction
+        .rejections
+        .iter()
+        .map(|owner| wallet.weight_of(owner))
+        .sum()
+}
+
+fn can_reach_threshold(wallet: &Wallet, transaction: &Transaction) -> bool {
+    wallet.total_weight() - rejected_weight(wallet, transaction)
+        >= required_weight(wallet, transaction)
+}
+
+fn init_transaction(
+    transaction: &mut Transaction,
+    wallet: &mut Account<Wallet>,
+    proposer: Pubkey,
+    bump: u8,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    transaction.wallet = wallet.key();
+    transaction.index = wallet.nonce;
+    transaction.bump = bump;
+    transaction.proposer = proposer;
+    transaction.is_executed = false;
+    // Proposers without voting rights don't approve their own proposals.
+    transaction.confirmations = if wallet.has_permission(&proposer, PERMISSION_VOTE) {
+        vec![proposer]
+    } else {
+        Vec::new()
+    };
+    transaction.created_at = Clock::get()?.unix_timestamp;
+    transaction.expires_at = resolve_expiry(wallet, transaction.created_at, expires_at)?;
+    transaction.requires_all_confirmations = false;
+    transaction.threshold = wallet.threshold;
+    transaction.time_lock_duration = wallet.time_lock_duration;
+    transaction.rejections = Vec::new();
+    transaction.vote_nonce = 0;
+    transaction.is_cancelled = false;
+
+    wallet.nonce += 1;
+
+    Ok(())
+}
+
+fn resolve_expiry(
+    wallet: &Wallet,
+    created_at: i64,
+    expires_at: Option<i64>,
+) -> Result<Option<i64>> {
+    match expires_at {
+        Some(expires_at) => {
+            require!(expires_at > created_at, ErrorCode::InvalidExpiry);
+            Ok(Some(expires_at))
+        }
+        None => match wallet.proposal_expiry {
+            Some(expiry) => Ok(Some(
+                created_at
+                    .checked_add(expiry)
+                    .ok_or(ErrorCode::InvalidExpiry)?,
+            )),
+            None => Ok(None),
+        },
+    }
+}
+
+fn is_expired(transaction: &Transaction, current_time: i64) -> bool {
+    transaction
+        .expires_at
+        .map_or(false, |expires_at| current_time >= expires_at)
+}
+
+/// Whether the transaction is a config change owners may push through while
+/// the wallet is paused.
+fn runs_while_paused(transaction: &Transaction) -> bool {
+    transaction
+        .config_action
+        .as_ref()
+        .map_or(false, ConfigAction::runs_while_paused)
+}
+
+/// Finished proposals whose rent can go back to the proposer.
+fn is_closable(wallet: &Wallet, transaction: &Transaction, current_time: i64) -> bool {
+    transaction.is_executed
+        || transaction.is_cancelled
+        || is_expired(transaction, current_time)
+        || transaction.index < wallet.stale_transaction_index
+}
+
+/// Grows or shrinks the wallet account to fit its owners, charging or
+/// refunding the rent difference to `payer`.
+fn resize_wallet<'info>(
+    wallet: &Account<'info, Wallet>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let info = wallet.to_account_info();
+    let new_len = Wallet::space(wallet.owners.len());
+    if new_len == info.data_len() {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?.minimum_balance(new_len);
+    let current = info.lamports();
+    if rent > current {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            rent - current,
+        )?;
+    } else if current > rent {
+        **info.try_borrow_mut_lamports()? -= current - rent;
+        **payer.to_account_info().try_borrow_mut_lamports()? += current - rent;
+    }
+
+    info.realloc(new_len, false)?;
+
+    Ok(())
+}
+
+/// Applies `action` to the wallet. Accounts it names, such as standing
+/// orders, are looked up by key in `accounts`; PDAs it creates are paid for
+/// by `payer`.
+fn apply_config_action<'info>(
+    wallet: &mut Account<'info, Wallet>,
+    action: &ConfigAction,
+    accounts: &[AccountInfo<'info>],
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    match *action {
+        ConfigAction::AddOwner {
+            owner,
+            permissions,
+            weight,
+        } => {
+            require!(wallet.owners.len() < MAX_OWNERS, ErrorCode::TooManyOwners);
+            require!(!wallet.is_owner(&owner), ErrorCode::OwnerAlreadyExists);
+            require!(
+                permissions != 0 && permissions & !PERMISSION_ALL == 0,
+                ErrorCode::InvalidPermissions
+            );
+            require!(weight > 0, ErrorCode::InvalidWeight);
+
+            wallet.owners.push(Member {
+                key: owner,
+                permissions,
+                weight,
+            });
+
+            wallet.invalidate_pending_transactions();
+
+            emit!(OwnerAdded {
+                wallet: wallet.key(),
+                owner,
+                permissions,
+                weight,
+            });
+        }
+        ConfigAction::RemoveOwner { owner } => {
+            require!(wallet.is_owner(&owner), ErrorCode::OwnerNotFound);
+            require!(wallet.owners.len() > 1, ErrorCode::CannotRemoveLastOwner);
+
+            wallet.owners.retain(|member| member.key != owner);
+            require!(wallet.has_every_permission(), ErrorCode::InvalidPermissions);
+            // Unpause votes are sized for the owners, so the wallet can shrink.
+            wallet.unpause_votes.retain(|&voter| voter != owner);
+
+            // Adjust threshold if necessary
+            wallet.threshold = wallet.threshold.min(wallet.total_weight());
+
+            wallet.invalidate_pending_transactions();
+
+            emit!(OwnerRemoved {
+                wallet: wallet.key(),
+                owner,
+            });
+        }
+        ConfigAction::SetPermissions { owner, permissions } => {
+            require!(
+                permissions != 0 && permissions & !PERMISSION_ALL == 0,
+                ErrorCode::InvalidPermissions
+            );
+            let member = wallet
+                .owners
+                .iter_mut()
+                .find(|member| member.key == owner)
+                .ok_or(ErrorCode::OwnerNotFound)?;
+            member.permissions = permissions;
+            require!(wallet.has_every_permission(), ErrorCode::InvalidPermissions);
+            if permissions & PERMISSION_VOTE == 0 {
+                wallet.unpause_votes.retain(|&voter| voter != owner);
+            }
+
+            // Adjust threshold if necessary
+            wallet.threshold = wallet.threshold.min(wallet.total_weight());
+
+            wallet.invalidate_pending_transactions();
+
+            emit!(PermissionsChanged {
+                wallet: wallet.key(),
+                owner,
+                permissions,
+            });
+        }
+        ConfigAction::SetWeight { owner, weight } => {
+            require!(weight > 0, ErrorCode::InvalidWeight);
+            let member = wallet
+                .owners
+                .iter_mut()
+                .find(|member| member.key == owner)
+                .ok_or(ErrorCode::OwnerNotFound)?;
+            member.weight = weight;
+
+            // Adjust threshold if necessary
+            wallet.threshold = wallet.threshold.min(wallet.total_weight());
+
+            wallet.invalidate_pending_transactions();
+
+            emit!(WeightChanged {
+                wallet: wallet.key(),
+                owner,
+                weight,
+            });
+        }
+        ConfigAction::AddGuardian { guardian } => {
+            require!(
+                wallet.guardians.len() < MAX_GUARDIANS,
+                ErrorCode::TooManyGuardians
+            );
+            require!(
+                !wallet.guardians.contains(&guardian),
+                ErrorCode::GuardianAlreadyExists
+            );
+
+            wallet.guardians.push(guardian);
+
+            emit!(GuardianAdded {
+                wallet: wallet.key(),
+                guardian,
+            });
+        }
+        ConfigAction::RemoveGuardian { guardian } => {
+            require!(
+                wallet.guardians.contains(&guardian),
+                ErrorCode::GuardianNotFound
+            );
+
+            wallet.guardians.retain(|&x| x != guardian);
+
+            emit!(GuardianRemoved {
+                wallet: wallet.key(),
+                guardian,
+            });
+        }
+        ConfigAction::SetProposalExpiry { expiry } => {
+            require!(expiry.map_or(true, |e| e > 0), ErrorCode::InvalidExpiry);
+
+            wallet.proposal_expiry = expiry;
+
+            emit!(ProposalExpirySet {
+                wallet: wallet.key(),
+                expiry,
+            });
+        }
+        ConfigAction::SetUnpausePolicy { quorum, cooldown } => {
+            require!(
+                quorum <= wallet.total_weight() && (0..=MAX_UNPAUSE_COOLDOWN).contains(&cooldown),
+                ErrorCode::InvalidThreshold
+            );
+
+            wallet.unpause_quorum = quorum;
+            wallet.unpause_cooldown = cooldown;
+
+            emit!(UnpausePolicySet {
+                wallet: wallet.key(),
+                quorum,
+                cooldown,
+            });
+        }
+        ConfigAction::ChangeThreshold { threshold } => {
+            require!(
+                threshold > 0 && threshold <= wallet.total_weight(),
+                ErrorCode::InvalidThreshold
+            );
+
+            wallet.threshold = threshold;
+
+            wallet.invalidate_pending_transactions();
+
+            emit!(ThresholdChanged {
+                wallet: wallet.key(),
+                threshold,
+            });
+        }
+        ConfigAction::ApproveStandingOrder { standing_order } => {
+            let mut order = load_wallet_account::<StandingOrder>(
+                wallet.key(),
+                standing_order,
+                accounts,
+                |order| order.wallet,
+            )?;
+            require!(!order.is_approved, ErrorCode::StandingOrderAlreadyApproved);
+            require!(!order.is_cancelled, ErrorCode::StandingOrderCancelled);
+
+            order.is_approved = true;
+            order.exit(&crate::ID)?;
+
+            emit!(StandingOrderApproved {
+                wallet: wallet.key(),
+                standing_order,
+            });
+        }
+        ConfigAction::CancelStandingOrder { standing_order } => {
+            let mut order = load_wallet_account::<StandingOrder>(
+                wallet.key(),
+                standing_order,
+                accounts,
+                |order| order.wallet,
+            )?;
+            require!(!order.is_cancelled, ErrorCode::StandingOrderCancelled);
+
+            order.is_cancelled = true;
+            order.exit(&crate::ID)?;
+
+            emit!(StandingOrderCancelled {
+                wallet: wallet.key(),
+                standing_order,
+            });
+        }
+        ConfigAction::ApproveVestingStream { vesting_stream } => {
+            let mut stream = load_wallet_account::<VestingStream>(
+                wallet.key(),
+                vesting_stream,
+                accounts,
+                |stream| stream.wallet,
+            )?;
+            require!(!stream.is_approved, ErrorCode::VestingStreamAlreadyApproved);
+
+            stream.is_approved = true;
+            stream.exit(&crate::ID)?;
+
+            emit!(VestingStreamApproved {
+                wallet: wallet.key(),
+                vesting_stream,
+            });
+        }
+        ConfigAction::RevokeVestingStream { vesting_stream } => {
+            let mut stream = load_wallet_account::<VestingStream>(
+                wallet.key(),
+                vesting_stream,
+                accounts,
+                |stream| stream.wallet,
+            )?;
+            require!(stream.is_revocable, ErrorCode::VestingStreamNotRevocable);
+            require!(stream.revoked_at.is_none(), ErrorCode::VestingStreamRevoked);
+
+            // Whatever vested up to now stays claimable.
+            let revoked_at = Clock::get()?.unix_timestamp;
+            stream.revoked_at = Some(revoked_at);
+            stream.exit(&crate::ID)?;
+
+            emit!(VestingStreamRevoked {
+                wallet: wallet.key(),
+                vesting_stream,
+                revoked_at,
+            });
+        }
+        ConfigAction::ApproveAddressBookEntry { entry } => {
+            let mut address_book_entry =
+                load_wallet_account::<AddressBookEntry>(wallet.key(), entry, accounts, |entry| {
+                    entry.wallet
+                })?;
+            require!(
+                address_book_entry.active_at.is_none(),
+                ErrorCode::AddressAlreadyListed
+            );
+
+            let active_at = Clock::get()?.unix_timestamp + wallet.address_book_delay;
+            address_book_entry.active_at = Some(active_at);
+            address_book_entry.exit(&crate::ID)?;
+
+            emit!(AddressBookEntryApproved {
+                wallet: wallet.key(),
+                entry,
+                active_at,
+            });
+        }
+        ConfigAction::RemoveAddressBookEntry { entry } => {
+            let mut address_book_entry =
+                load_wallet_account::<AddressBookEntry>(wallet.key(), entry, accounts, |entry| {
+                    entry.wallet
+                })?;
+
+            address_book_entry.active_at = None;
+            address_book_entry.exit(&crate::ID)?;
+
+            emit!(AddressBookEntryRemoved {
+                wallet: wallet.key(),
+                entry,
+            });
+        }
+        ConfigAction::SetDestinationPolicy { policy, delay } => {
+            require!(delay >= 0, ErrorCode::InvalidPolicy);
+
+            wallet.destination_policy = policy;
+            wallet.address_book_delay = delay;
+
+            emit!(DestinationPolicySet {
+                wallet: wallet.key(),
+                policy,
+                delay,
+            });
+        }
+        ConfigAction::ApproveAllowance { allowance } => {
+            let mut grant =
+                load_wallet_account::<Allowance>(wallet.key(), allowance, accounts, |grant| {
+                    grant.wallet
+                })?;
+            require!(!grant.is_approved, ErrorCode::AllowanceAlreadyApproved);
+            require!(!grant.is_revoked, ErrorCode::AllowanceRevoked);
+
+            grant.is_approved = true;
+            grant.period_start = Clock::get()?.unix_timestamp;
+            grant.exit(&crate::ID)?;
+
+            emit!(AllowanceApproved {
+                wallet: wallet.key(),
+                allowance,
+            });
+        }
+        ConfigAction::RevokeAllowance { allowance } => {
+            let mut grant =
+                load_wallet_account::<Allowance>(wallet.key(), allowance, accounts, |grant| {
+                    grant.wallet
+                })?;
+            require!(!grant.is_revoked, ErrorCode::AllowanceRevoked);
+
+            grant.is_revoked = true;
+            grant.exit(&crate::ID)?;
+
+            emit!(AllowanceRevoked {
+                wallet: wallet.key(),
+                allowance,
+            });
+        }
+        ConfigAction::SetSpendingLimit {
+            token_mint,
+            daily_limit,
+            monthly_limit,
+        } => {
+            let wallet_key = wallet.key();
+            let mint_key = token_mint.unwrap_or_default();
+            let (info, bump) = init_wallet_pda(
+                &[b"spending_limit", wallet_key.as_ref(), mint_key.as_ref()],
+                8 + 32 + 33 + 8 + 8 + 8 + 8 + 8 + 8 + 1,
+                accounts,
+                payer,
+                system_program,
+            )?;
+            let mut spending_limit = Account::<SpendingLimit>::try_from_unchecked(info)?;
+
+            // Keep the counters of an existing limit so raising or lowering it
+            // doesn't hand out a fresh allowance mid-window.
+            if spending_limit.wallet == Pubkey::default() {
+                let current_time = Clock::get()?.unix_timestamp;
+                spending_limit.wallet = wallet_key;
+                spending_limit.token_mint = token_mint;
+                spending_limit.daily_spent = 0;
+                spending_limit.monthly_spent = 0;
+                spending_limit.last_reset_day = current_time / SECONDS_PER_DAY;
+                spending_limit.last_reset_month = current_time / SECONDS_PER_MONTH;
+                spending_limit.bump = bump;
+            }
+
+            // Track how many mints are limited so instruction proposals know
+            // whether to escalate.
+            let was_limited = spending_limit.daily_limit != 0 || spending_limit.monthly_limit != 0;
+            match (was_limited, daily_limit != 0 || monthly_limit != 0) {
+                (false, true) => wallet.spending_limit_count += 1,
+                (true, false) => wallet.spending_limit_count -= 1,
+                _ => {}
+            }
+
+            spending_limit.daily_limit = daily_limit;
+            spending_limit.monthly_limit = monthly_limit;
+            spending_limit.exit(&crate::ID)?;
+
+            emit!(SpendingLimitSet {
+                wallet: wallet_key,
+                token_mint,
+                daily_limit,
+                monthly_limit,
+            });
+        }
+        ConfigAction::SetAmountPolicy {
+            token_mint,
+            ref tiers,
+        } => {
+            require!(tiers.len() <= MAX_POLICY_TIERS, ErrorCode::InvalidPolicy);
+            require!(
+                tiers
+                    .windows(2)
+                    .all(|pair| pair[0].min_amount < pair[1].min_amount),
+                ErrorCode::InvalidPolicy
+            );
+            for tier in tiers {
+                require!(
+                    tier.threshold > 0 && tier.threshold <= wallet.total_weight(),
+                    ErrorCode::InvalidThreshold
+                );
+                require!(tier.time_lock_duration >= 0, ErrorCode::InvalidPolicy);
+            }
+
+            let wallet_key = wallet.key();
+            let mint_key = token_mint.unwrap_or_default();
+            let (info, bump) = init_wallet_pda(
+                &[b"amount_policy", wallet_key.as_ref(), mint_key.as_ref()],
+                8 + 32 + 33 + 4 + (8 + 2 + 8) * MAX_POLICY_TIERS + 1,
+                accounts,
+                payer,
+                system_program,
+            )?;
+            let mut amount_policy = Account::<AmountPolicy>::try_from_unchecked(info)?;
+
+            // Track how many mints have tiers so instruction proposals know
+            // whether to escalate.
+            let had_tiers = !amount_policy.tiers.is_empty();
+            match (had_tiers, tiers.is_empty()) {
+                (false, false) => wallet.amount_policy_count += 1,
+                (true, true) => wallet.amount_policy_count -= 1,
+                _ => {}
+            }
+
+            amount_policy.wallet = wallet_key;
+            amount_policy.token_mint = token_mint;
+            amount_policy.tiers = tiers.clone();
+            amount_policy.bump = bump;
+            amount_policy.exit(&crate::ID)?;
+
+            emit!(AmountPolicySet {
+                wallet: wallet_key,
+                token_mint,
+                tiers: tiers.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks every address the transaction pays against the wallet's address
+/// book as of `current_time`. Batch token transfers are checked by the owner
+/// of the destination token account, which must be in `accounts`, so one
+/// entry covers a payee for single and batch transfers alike. Arbitrary
+/// instructions always count as unlisted.
+fn apply_destination_policy(
+    wallet: &Wallet,
+    transaction: &mut Transaction,
+    accounts: &[AccountInfo],
+    current_time: i64,
+) -> Result<()> {
+    if wallet.destination_policy == DestinationPolicy::Open {
+        return Ok(());
+    }
+
+    let mut destinations = Vec::new();
+    if transaction.amount > 0 {
+        destinations.push(Some(transaction.destination));
+    }
+    if transaction.instruction.is_some() {
+        destinations.push(None);
+    }
+    for action in &transaction.actions {
+        destinations.push(match action {
+            TransactionAction::SolTransfer { destination, .. } => Some(*destination),
+            TransactionAction::TokenTransfer { destination, .. } => {
+                let info = accounts
+                    .iter()
+                    .find(|info| info.key() == *destination)
+                    .ok_or(ErrorCode::MissingTransferAccount)?;
+                Some(InterfaceAccount::<TokenAccount>::try_from(info)?.owner)
+            }
+            TransactionAction::Instruction(_) => None,
+        });
+    }
+
+    let mut all_listed = true;
+    for destination in destinations {
+        all_listed &= match destination {
+            Some(address) => is_listed(transaction.wallet, address, accounts, current_time)?,
+            None => false,
+        };
+    }
+
+    if !all_listed {
+        require!(
+            wallet.destination_policy == DestinationPolicy::EscalateUnlisted,
+            ErrorCode::DestinationNotListed
+        );
+        transaction.requires_all_confirmations = true;
+    }
+
+    Ok(())
+}
+
+/// Payouts without an approval round of their own, such as standing order
+/// installments, vesting claims and allowance spends, can't escalate, so any
+/// destination policy, or `allowlisted_only`, requires `address` to be listed.
+fn check_payout_destination(
+    wallet: &Account<Wallet>,
+    address: Pubkey,
+    address_book_entry: &AccountInfo,
+    current_time: i64,
+    allowlisted_only: bool,
+) -> Result<()> {
+    if allowlisted_only || wallet.destination_policy != DestinationPolicy::Open {
+        require!(
+            is_listed(
+                wallet.key(),
+                address,
+                std::slice::from_ref(address_book_entry),
+                current_time,
+            )?,
+            ErrorCode::DestinationNotListed
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether `address` has an active entry in the wallet's address book. The
+/// entry PDA is looked up by key in `accounts`; a missing one counts as
+/// unlisted.
+fn is_listed(
+    wallet: Pubkey,
+    address: Pubkey,
+    accounts: &[AccountInfo],
+    current_time: i64,
+) -> Result<bool> {
+    let (key, _) = Pubkey::find_program_address(
+        &[b"address_book", wallet.as_ref(), address.as_ref()],
+        &crate::ID,
+    );
+    let Some(info) = accounts.iter().find(|info| info.key() == key) else {
+        return Ok(false);
+    };
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(false);
+    }
+
+    let entry: Account<AddressBookEntry> = Account::try_from(info)?;
+    Ok(entry
+        .active_at
+        .map_or(false, |active_at| current_time >= active_at))
+}
+
+/// Finds the PDA at `seeds` in `accounts`, creating it with `space` bytes
+/// paid by `payer` if it doesn't exist yet. New accounts are zeroed, so the
+/// caller initializes them through `Account::try_from_unchecked`. Returns
+/// the account and its bump.
+fn init_wallet_pda<'a, 'info>(
+    seeds: &[&[u8]],
+    space: usize,
+    accounts: &'a [AccountInfo<'info>],
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<(&'a AccountInfo<'info>, u8)> {
+    let (address, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+    let info = accounts
+        .iter()
+        .find(|info| info.key() == address)
+        .ok_or(ErrorCode::MissingConfigAccount)?;
+    if info.owner == &crate::ID {
+        return Ok((info, bump));
+    }
+
+    let bump_seed = [bump];
+    let signer_seeds = [seeds, &[&bump_seed[..]]].concat();
+    let signer_seeds: &[&[&[u8]]] = &[&signer_seeds];
+    let rent = Rent::get()?.minimum_balance(space);
+
+    // Anyone can send lamports to the address beforehand, which would make
+    // `create_account` fail, so fund, allocate and assign separately then.
+    if info.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let shortfall = rent.saturating_sub(info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Allocate {
+                    account_to_allocate: info.clone(),
+                },
+                signer_seeds,
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Assign {
+                    account_to_assign: info.clone(),
+                },
+                signer_seeds,
+            ),
+            &crate::ID,
+        )?;
+    }
+
+    Ok((info, bump))
+}
+
+/// Loads the program account `key` from `accounts`, checking it belongs to
+/// `wallet`.
+fn load_wallet_account<'info, T>(
+    wallet: Pubkey,
+    key: Pubkey,
+    accounts: &[AccountInfo<'info>],
+    wallet_of: impl Fn(&T) -> Pubkey,
+) -> Result<Account<'info, T>>
+where
+    T: AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    let info = accounts
+        .iter()
+        .find(|info| info.key() == key)
+        .ok_or(ErrorCode::MissingConfigAccount)?;
+    let account: Account<T> = Account::try_from(info)?;
+    require_keys_eq!(
+        wallet_of(&account),
+        wallet,
+        anchor_lang::error::ErrorCode::ConstraintHasOne
+    );
+
+    Ok(account)
+}
+
+fn load_spending_limit<'info>(
+    info: &AccountInfo<'info>,
+) -> Result<Option<Account<'info, SpendingLimit>>> {
+    // No limit has been set for this mint until the PDA is initialized.
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Account::try_from(info)?))
+}
+
+/// Total amount the transaction moves out of the vault, per mint.
+fn transaction_spends(transaction: &Transaction) -> Vec<(Option<Pubkey>, u64)> {
+    total_spends(transaction_transfers(transaction))
+}
+
+/// `transaction_spends` with each token transfer grossed up by its Token-2022
+/// transfer fee. Mint accounts are looked up by key in `accounts`.
+fn transaction_spends_with_fees(
+    transaction: &Transaction,
+    accounts: &[AccountInfo],
+) -> Result<Vec<(Option<Pubkey>, u64)>> {
+    let mut transfers = transaction_transfers(transaction);
+    for (token_mint, amount) in &mut transfers {
+        if let Some(token_mint) = token_mint {
+            let mint = accounts
+                .iter()
+                .find(|info| info.key() == *token_mint)
+                .ok_or(ErrorCode::MissingTransferAccount)?;
+            *amount = amount.saturating_add(transfer_fee(mint, *amount)?);
+        }
+    }
+
+    Ok(total_spends(transfers))
+}
+
+/// Every transfer out of the vault, in execution order.
+fn transaction_transfers(transaction: &Transaction) -> Vec<(Option<Pubkey>, u64)> {
+    let mut transfers = vec![(transaction.token_mint, transaction.amount)];
+    for action in &transaction.actions {
+        match action {
+            TransactionAction::SolTransfer { amount, .. } => transfers.push((None, *amount)),
+            TransactionAction::TokenTransfer {
+                token_mint, amount, ..
+            } => transfers.push((Some(*token_mint), *amount)),
+            TransactionAction::Instruction(_) => {}
+        }
+    }
+    transfers.retain(|&(_, amount)| amount > 0);
+
+    transfers
+}
+
+fn total_spends(transfers: Vec<(Option<Pubkey>, u64)>) -> Vec<(Option<Pubkey>, u64)> {
+    let mut spends: Vec<(Option<Pubkey>, u64)> = Vec::new();
+    for (token_mint, amount) in transfers {
+        match spends.iter_mut().find(|(mint, _)| *mint == token_mint) {
+            Some((_, total)) => *total = total.saturating_add(amount),
+            None => spends.push((token_mint, amount)),
+        }
+    }
+
+    spends
+}
+
+/// Fee the vault adds on top of `amount` so the destination receives it in
+/// full. Zero for legacy mints and mints without a transfer fee.
+fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => Ok(config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::InvalidTransferFee)?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Loads the initialized spending limits for `spends` from `accounts`, with
+/// their windows rolled over to `current_time`.
+fn load_spending_limits<'info>(
+    wallet: Pubkey,
+    spends: &[(Option<Pubkey>, u64)],
+    accounts: &[AccountInfo<'info>],
+    current_time: i64,
+) -> Result<Vec<(Account<'info, SpendingLimit>, u64)>> {
+    let mut spending_limits = Vec::new();
+    for &(token_mint, amount) in spends {
+        let (address, _) = Pubkey::find_program_address(
+            &[
+                b"spending_limit",
+                wallet.as_ref(),
+                token_mint.unwrap_or_default().as_ref(),
+            ],
+            &crate::ID,
+        );
+        let info = accounts
+            .iter()
+            .find(|info| info.key() == address)
+            .ok_or(ErrorCode::MissingSpendingLimit)?;
+
+        if let Some(mut spending_limit) = load_spending_limit(info)? {
+            reset_spending_windows(&mut spending_limit, current_time);
+            spending_limits.push((spending_limit, amount));
+        }
+    }
+
+    Ok(spending_limits)
+}
+
+/// Whether the transaction runs an arbitrary instruction, which can move any
+/// amount of any mint without showing up in its spends.
+fn carries_instruction(transaction: &Transaction) -> bool {
+    transaction.instruction.is_some()
+        || transaction
+            .actions
+            .iter()
+            .any(|action| matches!(action, TransactionAction::Instruction(_)))
+}
+
+/// Requires every owner when a spend would exceed its limit, or when the
+/// transaction carries an instruction and the wallet has any limit set.
+fn apply_spending_limits(
+    transaction: &mut Transaction,
+    wallet: &Wallet,
+    spending_limits: &[(Account<SpendingLimit>, u64)],
+) {
+    let over_limit = spending_limits
+        .iter()
+        .any(|(spending_limit, amount)| !is_within_spending_limit(spending_limit, *amount));
+    if over_limit || (carries_instruction(transaction) && wallet.spending_limit_count > 0) {
+        transaction.requires_all_confirmations = true;
+    }
+}
+
+/// Raises the transaction's threshold and time lock to the strictest tier its
+/// spends fall into. Every spent mint's policy PDA must be in `accounts`.
+/// Arbitrary instructions can move any amount of any mint, so once the
+/// wallet has amount policies, proposals carrying them need every owner.
+fn apply_amount_policies<'info>(
+    transaction: &mut Transaction,
+    wallet: &Wallet,
+    accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    if carries_instruction(transaction) && wallet.amount_policy_count > 0 {
+        transaction.requires_all_confirmations = true;
+    }
+
+    for (token_mint, amount) in transaction_spends(transaction) {
+        let (address, _) = Pubkey::find_program_address(
+            &[
+                b"amount_policy",
+                transaction.wallet.as_ref(),
+                token_mint.unwrap_or_default().as_ref(),
+            ],
+            &crate::ID,
+        );
+        let info = accounts
+            .iter()
+            .find(|info| info.key() == address)
+            .ok_or(ErrorCode::MissingAmountPolicy)?;
+
+        // No policy has been set for this mint until the PDA is initialized.
+        if info.owner != &crate::ID || info.data_is_empty() {
+            continue;
+        }
+        let amount_policy: Account<AmountPolicy> = Account::try_from(info)?;
+        if let Some(tier) = amount_policy.tier_for(amount) {
+            transaction.threshold = transaction.threshold.max(tier.threshold);
+            transaction.time_lock_duration =
+                transaction.time_lock_duration.max(tier.time_lock_duration);
+        }
+    }
+
+    Ok(())
+}
+
+fn reset_spending_windows(spending_limit: &mut SpendingLimit, current_time: i64) {
+    let current_day = current_time / SECONDS_PER_DAY;
+    if current_day != spending_limit.last_reset_day {
+        spending_limit.daily_spent = 0;
+        spending_limit.last_reset_day = current_day;
+    }
+
+    let current_month = current_time / SECONDS_PER_MONTH;
+    if current_month != spending_limit.last_reset_month {
+        spending_limit.monthly_spent = 0;
+        spending_limit.last_reset_month = current_month;
+    }
+}
+
+fn is_within_spending_limit(spending_limit: &SpendingLimit, amount: u64) -> bool {
+    let within = |limit: u64, spent: u64| {
+        limit == 0
+            || spent
+                .checked_add(amount)
+                .map_or(false, |total| total <= limit)
+    };
+
+    within(spending_limit.daily_limit, spending_limit.daily_spent)
+        && within(spending_limit.monthly_limit, spending_limit.monthly_spent)
+}
+
+/// Charges `spent` to the wallet's limit for `token_mint`, failing instead of
+/// escalating: payouts without an approval round must fit it outright.
+fn charge_spending_limits_strict<'info>(
+    wallet: Pubkey,
+    token_mint: Option<Pubkey>,
+    spent: u64,
+    spending_limit: &AccountInfo<'info>,
+    current_time: i64,
+) -> Result<()> {
+    let spending_limits = load_spending_limits(
+        wallet,
+        &[(token_mint, spent)],
+        std::slice::from_ref(spending_limit),
+        current_time,
+    )?;
+    for (mut spending_limit, amount) in spending_limits {
+        require!(
+            is_within_spending_limit(&spending_limit, amount),
+            ErrorCode::SpendingLimitExceeded
+        );
+        update_spending_limit(&mut spending_limit, amount);
+        spending_limit.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
+
+fn update_spending_limit(spending_limit: &mut SpendingLimit, amount: u64) {
+    spending_limit.daily_spent = spending_limit.daily_spent.saturating_add(amount);
+    spending_limit.monthly_spent = spending_limit.monthly_spent.saturating_add(amount);
+}
+
+fn transfer_from_vault<'info>(
+    accounts: &ExecuteTransaction<'info>,
+    destination: Pubkey,
+    amount: u64,
+    token_mint: Option<Pubkey>,
+    remaining_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    match token_mint {
+        None => {
+            let destination_account = accounts
+                .destination
+                .as_ref()
+                .ok_or(ErrorCode::MissingTransferAccount)?;
+            require_keys_eq!(
+                destination_account.key(),
+                destination,
+                ErrorCode::InvalidDestination
+            );
+
+            transfer_sol(
+                &accounts.system_program,
+                &accounts.vault,
+                destination_account,
+                amount,
+                signer_seeds,
+            )
+        }
+        Some(mint) => {
+            let (vault_token_account, destination_token_account, mint_account, token_program) =
+                match (
+                    &accounts.vault_token_account,
+                    &accounts.destination_token_account,
+                    &accounts.mint,
+                    &accounts.token_program,
+                ) {
+                    (Some(from), Some(to), Some(mint), Some(program)) => (from, to, mint, program),
+                    _ => return err!(ErrorCode::MissingTransferAccount),
+                };
+            require_keys_eq!(mint_account.key(), mint, ErrorCode::InvalidTokenAccount);
+
+            // Pay vendors who have no token account yet by creating their
+            // associated token account.
+            if destination_token_account.data_is_empty() {
+                create_destination_token_account(
+                    accounts,
+                    destination,
+                    mint_account,
+                    token_program,
+                )?;
+            }
+            let destination_token_account = destination_token_account.to_account_info();
+            let destination_token_account =
+                InterfaceAccount::<TokenAccount>::try_from(&destination_token_account)?;
+
+            require_keys_eq!(
+                vault_token_account.owner,
+                accounts.vault.key(),
+                ErrorCode::InvalidTokenAccount
+            );
+            require_keys_eq!(
+                vault_token_account.mint,
+                mint,
+                ErrorCode::InvalidTokenAccount
+            );
+            require_keys_eq!(
+                destination_token_account.owner,
+                destination,
+                ErrorCode::InvalidTokenAccount
+            );
+            require_keys_eq!(
+                destination_token_account.mint,
+                mint,
+                ErrorCode::InvalidTokenAccount
+            );
+
+            transfer_tokens(
+                &token_program.to_account_info(),
+                &vault_token_account.to_account_info(),
+                mint_account,
+                &destination_token_account.to_account_info(),
+                &accounts.vault,
+                amount,
+                remaining_accounts,
+                signer_seeds,
+            )
+        }
+    }
+}
+
+/// Accounts a payout made outside `execute_transaction` moves funds
+/// through. `recipient` is only needed for SOL, the token accounts only for
+/// tokens.
+struct PayoutAccounts<'a, 'info> {
+    vault: &'a AccountInfo<'info>,
+    recipient: Option<&'a AccountInfo<'info>>,
+    vault_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    recipient_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    mint: Option<&'a InterfaceAccount<'info, Mint>>,
+    token_program: Option<&'a Interface<'info, TokenInterface>>,
+    system_program: &'a Program<'info, System>,
+}
+
+/// Pays `amount` of `token_mint`, or SOL, from the vault to `recipient`
+/// after checking the passed accounts belong to the vault and recipient.
+fn pay_from_vault<'info>(
+    accounts: &PayoutAccounts<'_, 'info>,
+    recipient: Pubkey,
+    token_mint: Option<Pubkey>,
+    amount: u64,
+    extra_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    match token_mint {
+        None => {
+            let recipient_account = accounts
+                .recipient
+                .ok_or(ErrorCode::MissingTransferAccount)?;
+            require_keys_eq!(
+                recipient_account.key(),
+                recipient,
+                ErrorCode::InvalidDestination
+            );
+
+            transfer_sol(
+                accounts.system_program,
+                accounts.vault,
+                recipient_account,
+                amount,
+                signer_seeds,
+            )
+        }
+        Some(mint) => {
+            let (vault_token_account, recipient_token_account, mint_account, token_program) = match (
+                accounts.vault_token_account,
+                accounts.recipient_token_account,
+                accounts.mint,
+                accounts.token_program,
+            ) {
+                (Some(from), Some(to), Some(mint), Some(program)) => (from, to, mint, program),
+                _ => return err!(ErrorCode::MissingTransferAccount),
+            };
+            require_keys_eq!(mint_account.key(), mint, ErrorCode::InvalidTokenAccount);
+            require_keys_eq!(
+                vault_token_account.owner,
+                accounts.vault.key(),
+                ErrorCode::InvalidTokenAccount
+            );
+            require_keys_eq!(
+                vault_token_account.mint,
+                mint,
+                ErrorCode::InvalidTokenAccount
+            );
+            require_keys_eq!(
+                recipient_token_account.owner,
+                recipient,
+                ErrorCode::InvalidTokenAccount
+            );
+            require_keys_eq!(
+                recipient_token_account.mint,
+                mint,
+                ErrorCode::InvalidTokenAccount
+            );
+
+            transfer_tokens(
+                &token_program.to_account_info(),
+                &vault_token_account.to_account_info(),
+                mint_account,
+                &recipient_token_account.to_account_info(),
+                accounts.vault,
+                amount,
+                extra_accounts,
+                signer_seeds,
+            )
+        }
+    }
+}
+
+/// Creates `destination`'s associated token account for `mint`, paid by the
+/// executor.
+fn create_destination_token_account<'info>(
+    accounts: &ExecuteTransaction<'info>,
+    destination: Pubkey,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let (owner, token_account, associated_token_program) = match (
+        &accounts.destination,
+        &accounts.destination_token_account,
+        &accounts.associated_token_program,
+    ) {
+        (Some(owner), Some(token_account), Some(program)) => (owner, token_account, program),
+        _ => return err!(ErrorCode::MissingTransferAccount),
+    };
+    require_keys_eq!(owner.key(), destination, ErrorCode::InvalidDestination);
+    require_keys_eq!(
+        token_account.key(),
+        associated_token::get_associated_token_address_with_program_id(
+            &destination,
+            &mint.key(),
+            &token_program.key(),
+        ),
+        ErrorCode::InvalidTokenAccount
+    );
+
+    associated_token::create_idempotent(CpiContext::new(
+        associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: accounts.executor.to_account_info(),
+            associated_token: token_account.to_account_info(),
+            authority: owner.to_account_info(),
+            mint: mint.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        },
+    ))
+}
+
+/// Runs a batch's actions in order. Accounts are looked up by key in
+/// `remaining_accounts`.
+fn execute_actions<'info>(
+    accounts: &ExecuteTransaction<'info>,
+    actions: &[TransactionAction],
+    remaining_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let find_account = |key: Pubkey| {
+        remaining_accounts
+            .iter()
+            .find(|info| info.key() == key)
+            .ok_or_else(|| error!(ErrorCode::MissingTransferAccount))
+    };
+    let vault = accounts.vault.to_account_info();
+
+    for action in actions {
+        match action {
+            TransactionAction::SolTransfer {
+                destination,
+                amount,
+            } => {
+                transfer_sol(
+                    &accounts.system_program,
+                    &vault,
+                    find_account(*destination)?,
+                    *amount,
+                    signer_seeds,
+                )?;
+            }
+            TransactionAction::TokenTransfer {
+                token_mint,
+                source,
+                destination,
+                amount,
+            } => {
+                let mint = InterfaceAccount::<Mint>::try_from(find_account(*token_mint)?)?;
+                // Each mint is moved by the token program that owns it, so
+                // one batch can mix legacy and Token-2022 transfers.
+                let token_program = match &accounts.token_program {
+                    Some(program) if program.key() == *mint.to_account_info().owner => {
+                        program.to_account_info()
+                    }
+                    _ => find_account(*mint.to_account_info().owner)?.clone(),
+                };
+                let source = InterfaceAccount::<TokenAccount>::try_from(find_account(*source)?)?;
+                let destination =
+                    InterfaceAccount::<TokenAccount>::try_from(find_account(*destination)?)?;
+                require_keys_eq!(source.owner, vault.key(), ErrorCode::InvalidTokenAccount);
+                require_keys_eq!(source.mint, *token_mint, ErrorCode::InvalidTokenAccount);
+                require_keys_eq!(
+                    destination.mint,
+                    *token_mint,
+                    ErrorCode::InvalidTokenAccount
+                );
+
+                transfer_tokens(
+                    &token_program,
+                    &source.to_account_info(),
+                    &mint,
+                    &destination.to_account_info(),
+                    &vault,
+                    *amount,
+                    remaining_accounts,
+                    signer_seeds,
+                )?;
+            }
+            TransactionAction::Instruction(ix) => {
+                execute_instruction(ix, &vault, remaining_accounts, signer_seeds)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn transfer_sol<'info>(
+    system_program: &Program<'info, System>,
+    vault: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = system_program::Transfer {
+        from: vault.clone(),
+        to: destination.clone(),
+    };
+    let cpi_ctx =
+        CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer_seeds);
+
+    system_program::transfer(cpi_ctx, amount)
+}
+
+/// Sends `amount` plus any Token-2022 transfer fee, so `to` receives `amount`.
+///
+/// For Token-2022 mints `extra_accounts` are appended to the transfer so a
+/// transfer hook finds its program, validation account and the accounts that
+/// account lists; callers pass their remaining accounts.
+#[allow(clippy::too_many_arguments)]
+fn transfer_tokens<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    amount: u64,
+    extra_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    let gross_amount = amount.saturating_add(transfer_fee(&mint_info, amount)?);
+
+    if token_program.key() == spl_token_2022::ID && !extra_accounts.is_empty() {
+        let mut ix = spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            from.key,
+            mint_info.key,
+            to.key,
+            vault.key,
+            &[],
+            gross_amount,
+            mint.decimals,
+        )?;
+        ix.accounts
+            .extend(extra_accounts.iter().map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: false,
+                is_writable: account.is_writable,
+            }));
+
+        let mut account_infos = vec![from.clone(), mint_info, to.clone(), vault.clone()];
+        account_infos.extend_from_slice(extra_accounts);
+        invoke_signed(&ix, &account_infos, signer_seeds)?;
+        return Ok(());
+    }
+
+    let cpi_accounts = TransferChecked {
+        from: from.clone(),
+        mint: mint_info,
+        to: to.clone(),
+        authority: vault.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+
+    token_interface::transfer_checked(cpi_ctx, gross_amount, mint.decimals)
+}
+
+fn execute_instruction<'info>(
+    instruction: &TransactionInstruction,
+    vault: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    // The vault is part of the fixed accounts, so it has to be appended for
+    // instructions that reference it.
+    let mut account_infos = remaining_accounts.to_vec();
+    account_infos.push(vault.clone());
+
+    invoke_signed(&instruction.to_instruction(), &account_infos, signer_seeds)?;
+
+    Ok(())
+}
+
+// Account structures
+#[account]
+pub struct Wallet {
+    /// Creator and caller-chosen index the wallet address is derived from.
+    pub authority: Pubkey,
+    pub index: u64,
+    pub owners: Vec<Member>,
+    /// Combined member weight a proposal needs before it can execute.
+    pub threshold: u16,
+    pub time_lock_duration: i64,
+    pub nonce: u64,
+    pub bump: u8,
+    pub is_paused: bool,
+    pub vault_bump: u8,
+    pub guardians: Vec<Pubkey>,
+    pub recovery_count: u64,
+    /// Default lifetime in seconds for new proposals; `None` never expires.
+    pub proposal_expiry: Option<i64>,
+    pub paused_by: Option<Pubkey>,
+    pub paused_at: i64,
+    pub pause_reason: String,
+    pub unpause_votes: Vec<Pubkey>,
+    /// Vote weight needed to unpause; zero falls back to `threshold`.
+    pub unpause_quorum: u16,
+    /// Seconds after pausing before the wallet may be unpaused.
+    pub unpause_cooldown: i64,
+    /// Proposals with a lower index were created under an older owner set
+    /// or threshold and can no longer be confirmed or executed.
+    pub stale_transaction_index: u64,
+    pub standing_order_count: u64,
+    pub vesting_stream_count: u64,
+    pub destination_policy: DestinationPolicy,
+    /// Seconds between approving an address book entry and it taking effect.
+    pub address_book_delay: i64,
+    pub allowance_count: u64,
+    /// Mints whose amount policy has at least one tier.
+    pub amount_policy_count: u16,
+    /// Mints with a non-zero daily or monthly spending limit.
+    pub spending_limit_count: u16,
+}
+
+impl Wallet {
+    /// Account size for a wallet with `owner_count` owners.
+    pub fn space(owner_count: usize) -> usize {
+        8 + 32
+            + 8
+            + 4
+            + 34 * owner_count
+            + 2
+            + 8
+            + 8
+            + 1
+            + 1
+            + 1
+            + 4
+            + 32 * MAX_GUARDIANS
+            + 8
+            + 1
+            + 8
+            + 33
+            + 8
+            + 4
+            + MAX_PAUSE_REASON_LEN
+            + 4
+            + 32 * owner_count
+            + 2
+            + 8
+            + 8
+            + 8
+            + 8
+            + 1
+            + 8
+            + 8
+            + 2
+            + 2
+    }
+
+    pub fn is_owner(&self, key: &Pubkey) -> bool {
+        self.owners.iter().any(|member| member.key == *key)
+    }
+
+    pub fn has_permission(&self, key: &Pubkey, permission: u8) -> bool {
+        self.owners
+            .iter()
+            .any(|member| member.key == *key && member.permissions & permission != 0)
+    }
+
+    /// Weight `key` contributes toward the threshold; zero unless it can vote.
+    pub fn weight_of(&self, key: &Pubkey) -> u16 {
+        self.owners
+            .iter()
+            .find(|member| member.key == *key && member.permissions & PERMISSION_VOTE != 0)
+            .map_or(0, |member| member.weight as u16)
+    }
+
+    /// Retires every proposal created so far; called on owner set or
+    /// threshold changes.
+    pub fn invalidate_pending_transactions(&mut self) {
+        self.stale_transaction_index = self.nonce;
+    }
+
+    pub fn required_unpause_votes(&self) -> u16 {
+        let quorum = if self.unpause_quorum == 0 {
+            self.threshold
+        } else {
+            self.unpause_quorum
+        };
+        quorum.min(self.total_weight())
+    }
+
+    /// Combined weight of every voting member.
+    pub fn total_weight(&self) -> u16 {
+        self.owners
+            .iter()
+            .filter(|member| member.permissions & PERMISSION_VOTE != 0)
+            .map(|member| member.weight as u16)
+            .sum()
+    }
+
+    fn has_every_permission(&self) -> bool {
+        [PERMISSION_PROPOSE, PERMISSION_VOTE, PERMISSION_EXECUTE]
+            .iter()
+            .all(|&permission| {
+                self.owners
+                    .iter()
+                    .any(|member| member.permissions & permission != 0)
+            })
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Member {
+    pub key: Pubkey,
+    /// Bitmask of `PERMISSION_*` flags.
+    pub permissions: u8,
+    pub weight: u8,
+}
+
+#[account]
+pub struct Transaction {
+    pub wallet: Pubkey,
+    pub index: u64,
+    pub bump: u8,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub instruction: Option<TransactionInstruction>,
+    pub token_mint: Option<Pubkey>,
+    pub proposer: Pubkey,
+    pub is_executed: bool,
+    pub confirmations: Vec<Pubkey>,
+    pub created_at: i64,
+    pub requires_all_confirmations: bool,
+    /// Threshold and time lock in force when the proposal was created.
+    pub threshold: u16,
+    pub time_lock_duration: i64,
+    pub config_action: Option<ConfigAction>,
+    pub rejections: Vec<Pubkey>,
+    pub is_cancelled: bool,
+    pub expires_at: Option<i64>,
+    pub actions: Vec<TransactionAction>,
+    /// Bumped on every rejection; part of the off-chain confirmation message.
+    pub vote_nonce: u32,
+}
+
+impl Transaction {
+    /// Confirmations and rejections are sized for `owner_count`; owner set
+    /// changes retire pending proposals, so they never outgrow it.
+    pub fn space(
+        owner_count: usize,
+        instruction: Option<&TransactionInstruction>,
+        actions: &[TransactionAction],
+        config_action: Option<&ConfigAction>,
+    ) -> usize {
+        8 + 32
+            + 8
+            + 1
+            + 32
+            + 8
+            + 1
+            + instruction.map_or(0, TransactionInstruction::space)
+            + 33
+            + 32
+            + 1
+            + 4
+            + 32 * owner_count
+            + 8
+            + 1
+            + 2
+            + 8
+            + 1
+            + config_action.map_or(0, ConfigAction::space)
+            + 4
+            + 32 * owner_count
+            + 1
+            + 1
+            + 8
+            + 4
+            + actions.iter().map(TransactionAction::space).sum::<usize>()
+            + 4
+    }
+}
+
+/// One step of a batch transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum TransactionAction {
+    SolTransfer {
+        destination: Pubkey,
+        amount: u64,
+    },
+    /// Moves tokens from the vault-owned `source` token account to the
+    /// `destination` token account.
+    TokenTransfer {
+        token_mint: Pubkey,
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    Instruction(TransactionInstruction),
+}
+
+impl TransactionAction {
+    pub fn space(&self) -> usize {
+        1 + match self {
+            TransactionAction::SolTransfer { .. } => 32 + 8,
+            TransactionAction::TokenTransfer { .. } => 32 + 32 + 32 + 8,
+            TransactionAction::Instruction(ix) => ix.space(),
+        }
+    }
+}
+
+/// A change to the wallet's own configuration, applied by `execute_config_transaction`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum ConfigAction {
+    AddOwner {
+        owner: Pubkey,
+        permissions: u8,
+        weight: u8,
+    },
+    RemoveOwner {
+        owner: Pubkey,
+    },
+    SetPermissions {
+        owner: Pubkey,
+        permissions: u8,
+    },
+    SetWeight {
+        owner: Pubkey,
+        weight: u8,
+    },
+    ChangeThreshold {
+        threshold: u16,
+    },
+    AddGuardian {
+        guardian: Pubkey,
+    },
+    RemoveGuardian {
+        guardian: Pubkey,
+    },
+    SetProposalExpiry {
+        expiry: Option<i64>,
+    },
+    SetUnpausePolicy {
+        quorum: u16,
+        cooldown: i64,
+    },
+    ApproveStandingOrder {
+        standing_order: Pubkey,
+    },
+    CancelStandingOrder {
+        standing_order: Pubkey,
+    },
+    ApproveVestingStream {
+        vesting_stream: Pubkey,
+    },
+    RevokeVestingStream {
+        vesting_stream: Pubkey,
+    },
+    ApproveAddressBookEntry {
+        entry: Pubkey,
+    },
+    RemoveAddressBookEntry {
+        entry: Pubkey,
+    },
+    SetDestinationPolicy {
+        policy: DestinationPolicy,
+        delay: i64,
+    },
+    ApproveAllowance {
+        allowance: Pubkey,
+    },
+    RevokeAllowance {
+        allowance: Pubkey,
+    },
+    /// Creates or updates the mint's spending limit, keeping what has been
+    /// spent in the current windows. Zero leaves a window unrestricted.
+    SetSpendingLimit {
+        token_mint: Option<Pubkey>,
+        daily_limit: u64,
+        monthly_limit: u64,
+    },
+    /// Replaces the amount tiers for `token_mint`. Tiers can only tighten the
+    /// wallet-wide threshold and time lock, never loosen them.
+    SetAmountPolicy {
+        token_mint: Option<Pubkey>,
+        tiers: Vec<PolicyTier>,
+    },
+}
+
+impl ConfigAction {
+    /// Actions that strip a key's rights, so a compromised pauser can be
+    /// removed without unpausing first.
+    pub fn runs_while_paused(&self) -> bool {
+        matches!(
+            self,
+            ConfigAction::RemoveOwner { .. }
+                | ConfigAction::SetPermissions { .. }
+                | ConfigAction::RemoveGuardian { .. }
+        )
+    }
+
+    pub fn space(&self) -> usize {
+        1 + match self {
+            ConfigAction::SetSpendingLimit { .. } => 33 + 8 + 8,
+            ConfigAction::SetAmountPolicy { tiers, .. } => 33 + 4 + (8 + 2 + 8) * tiers.len(),
+            _ => 32 + 1 + 1,
+        }
+    }
+}
+
+/// Per-mint spending caps. A limit of zero leaves that window unrestricted.
+#[account]
+pub struct SpendingLimit {
+    pub wallet: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub daily_limit: u64,
+    pub monthly_limit: u64,
+    pub daily_spent: u64,
+    pub monthly_spent: u64,
+    pub last_reset_day: i64,
+    pub last_reset_month: i64,
+    pub bump: u8,
+}
+
+/// Per-mint amount bands, each requiring its own threshold and time lock.
+#[account]
+pub struct AmountPolicy {
+    pub wallet: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    /// Sorted by ascending `min_amount`.
+    pub tiers: Vec<PolicyTier>,
+    pub bump: u8,
+}
+
+impl AmountPolicy {
+    /// The highest tier whose `min_amount` the amount reaches.
+    pub fn tier_for(&self, amount: u64) -> Option<&PolicyTier> {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| amount >= tier.min_amount)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PolicyTier {
+    pub min_amount: u64,
+    pub threshold: u16,
+    pub time_lock_duration: i64,
+}
+
+/// A guardian-initiated request to replace a lost owner key.
+#[account]
+pub struct SocialRecovery {
+    pub wallet: Pubkey,
+    pub recovery_id: u64,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub confirmations: Vec<Pubkey>,
+    pub is_executed: bool,
+    pub is_cancelled: bool,
+    pub initiated_at: i64,
+    pub bump: u8,
+}
+
+/// A recurring payment from the vault, released by a permissionless crank
+/// once approved.
+#[account]
+pub struct StandingOrder {
+    pub wallet: Pubkey,
+    pub order_id: u64,
+    pub proposer: Pubkey,
+    pub recipient: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    /// Paid per installment.
+    pub amount: u64,
+    /// Seconds between installments.
+    pub period: i64,
+    pub start_time: i64,
+    /// No installment falls due after this time.
+    pub end_time: Option<i64>,
+    pub max_occurrences: Option<u32>,
+    pub occurrences_paid: u32,
+    pub is_approved: bool,
+    pub is_cancelled: bool,
+    pub bump: u8,
+}
+
+impl StandingOrder {
+    pub fn next_due_at(&self) -> i64 {
+        self.start_time
+            .saturating_add(self.period.saturating_mul(self.occurrences_paid as i64))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.max_occurrences
+            .map_or(false, |max| self.occurrences_paid >= max)
+            || self
+                .end_time
+                .map_or(false, |end_time| self.next_due_at() > end_time)
+    }
+}
+
+/// Tokens or SOL unlocking linearly from `start_time` to `end_time`, with
+/// nothing claimable before `cliff_time`.
+#[account]
+pub struct VestingStream {
+    pub wallet: Pubkey,
+    pub stream_id: u64,
+    pub proposer: Pubkey,
+    pub beneficiary: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub claimed_amount: u64,
+    pub is_revocable: bool,
+    pub is_approved: bool,
+    /// Vesting stops at this time once revoked.
+    pub revoked_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl VestingStream {
+    pub fn vested_amount(&self, current_time: i64) -> u64 {
+        let time = self
+            .revoked_at
+            .map_or(current_time, |revoked_at| current_time.min(revoked_at));
+        if time < self.cliff_time {
+            return 0;
+        }
+        if time >= self.end_time {
+            return self.total_amount;
+        }
+
+        let elapsed = (time - self.start_time) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        (self.total_amount as u128 * elapsed / duration) as u64
+    }
+}
+
+/// How proposals paying addresses outside the address book are treated.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DestinationPolicy {
+    /// Any destination is accepted.
+    Open,
+    /// Proposals paying unlisted destinations are refused.
+    AllowlistOnly,
+    /// Proposals paying unlisted destinations need every owner.
+    EscalateUnlisted,
+}
+
+/// A labeled destination in the wallet's address book.
+#[account]
+pub struct AddressBookEntry {
+    pub wallet: Pubkey,
+    pub address: Pubkey,
+    pub label: String,
+    /// When the entry takes effect; `None` until approved or once removed.
+    pub active_at: Option<i64>,
+    pub bump: u8,
+}
+
+/// A budget the grantee may spend from the vault on their own signature,
+/// refilled every `period`.
+#[account]
+pub struct Allowance {
+    pub wallet: Pubkey,
+    pub allowance_id: u64,
+    pub proposer: Pubkey,
+    pub grantee: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    /// Spendable per period, transfer fees included.
+    pub amount: u64,
+    /// Seconds per period.
+    pub period: i64,
+    pub period_start: i64,
+    pub spent_in_period: u64,
+    /// Only destinations in the address book may be paid.
+    pub allowlisted_only: bool,
+    pub is_approved: bool,
+    pub is_revoked: bool,
+    pub bump: u8,
+}
+
+impl Allowance {
+    /// Moves to the period containing `current_time`, clearing the spend.
+    pub fn roll_period(&mut self, current_time: i64) {
+        let elapsed = current_time.saturating_sub(self.period_start);
+        if elapsed >= self.period {
+            self.period_start += elapsed - elapsed % self.period;
+            self.spent_in_period = 0;
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// An instruction the vault signs through `invoke_signed` once the transaction executes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl TransactionInstruction {
+    pub fn space(&self) -> usize {
+        32 + 4 + self.accounts.len() * (32 + 1 + 1) + 4 + self.data.len()
+    }
+
+    fn to_instruction(&self) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+// Context structures
+#[derive(Accounts)]
+#[instruction(
+    owners: Vec<Member>,
+    threshold: u16,
+    time_lock_duration: i64,
+    bump: u8,
+    index: u64
+)]
+pub struct InitializeWallet<'info> {
+    #[account(
+        init,
+        seeds = [b"wallet", authority.key().as_ref(), index.to_le_bytes().as_ref()],
+        bump,
+        payer = authority,
+        space = Wallet::space(owners.len())
+    )]
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    destination: Pubkey,
+    amount: u64,
+    instruction: Option<TransactionInstruction>,
+    token_mint: Option<Pubkey>,
+    expires_at: Option<i64>
+)]
+pub struct CreateTransaction<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = Transaction::space(wallet.owners.len(), instruction.as_ref(), &[], None),
+        seeds = [b"transaction", wallet.key().as_ref(), wallet.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+    /// CHECK: Spending limit PDA for `token_mint`; only read if it has been initialized.
+    #[account(
+        seeds = [b"spending_limit", wallet.key().as_ref(), token_mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub spending_limit: UncheckedAccount<'info>,
+    /// CHECK: Amount policy PDA for `token_mint`; only read if it has been initialized.
+    #[account(
+        seeds = [b"amount_policy", wallet.key().as_ref(), token_mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub amount_policy: UncheckedAccount<'info>,
+    /// CHECK: Address book entry PDA for `destination`; only read if it has been initialized.
+    #[account(
+        seeds = [b"address_book", wallet.key().as_ref(), destination.as_ref()],
+        bump
+    )]
+    pub address_book_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(actions: Vec<TransactionAction>)]
+pub struct CreateBatchTransaction<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = Transaction::space(wallet.owners.len(), None, &actions, None),
+        seeds = [b"transaction", wallet.key().as_ref(), wallet.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmTransaction<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub transaction: Account<'info, Transaction>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmTransactionWithSignatures<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub transaction: Account<'info, Transaction>,
+    /// CHECK: Instructions sysvar, read for the Ed25519 signature checks.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RejectTransaction<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub transaction: Account<'info, Transaction>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransaction<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub transaction: Account<'info, Transaction>,
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTransaction<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet, has_one = proposer, close = proposer)]
+    pub transaction: Account<'info, Transaction>,
+    /// CHECK: Receives the reclaimed rent; checked against `transaction.proposer`.
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTransactions<'info> {
+    pub wallet: Account<'info, Wallet>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTransaction<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut, seeds = [b"vault", wallet.key().as_ref()], bump = wallet.vault_bump)]
+    pub vault: SystemAccount<'info>,
+    /// CHECK: Spending limit PDA for the transaction's mint; only used if it has been initialized.
+    #[account(
+        mut,
+        seeds = [
+            b"spending_limit",
+            wallet.key().as_ref(),
+            transaction.token_mint.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub spending_limit: UncheckedAccount<'info>,
+    /// CHECK: Address book entry PDA for `transaction.destination`; only read if it has been initialized.
+    #[account(
+        seeds = [b"address_book", wallet.key().as_ref(), transaction.destination.as_ref()],
+        bump
+    )]
+    pub address_book_entry: UncheckedAccount<'info>,
+    /// CHECK: Receives lamports only; checked against `transaction.destination`.
+    #[account(mut)]
+    pub destination: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: Created as `destination`'s associated token account if empty,
+    /// then checked against `transaction.destination` and the mint.
+    #[account(mut)]
+    pub destination_token_account: Option<UncheckedAccount<'info>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    /// Pays rent for a destination token account created during execution.
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(action: ConfigAction)]
+pub struct CreateConfigTransaction<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = Transaction::space(wallet.owners.len(), None, &[], Some(&action)),
+        seeds = [b"transaction", wallet.key().as_ref(), wallet.nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigTransaction<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub transaction: Account<'info, Transaction>,
+    /// Pays for or receives the rent difference when the owner count changes.
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitiateSocialRecovery<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = guardian,
+        space = 8 + 32 + 8 + 32 + 32 + 4 + 32 * 10 + 1 + 1 + 8 + 1,
+        seeds = [b"recovery", wallet.key().as_ref(), wallet.recovery_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub recovery: Account<'info, SocialRecovery>,
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmSocialRecovery<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub recovery: Account<'info, SocialRecovery>,
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSocialRecovery<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub recovery: Account<'info, SocialRecovery>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSocialRecovery<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub recovery: Account<'info, SocialRecovery>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PauseWallet<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    pub pauser: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnpauseWallet<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateStandingOrder<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + 32 + 8 + 32 + 32 + 33 + 8 + 8 + 8 + 9 + 5 + 4 + 1 + 1 + 1,
+        seeds = [b"standing_order", wallet.key().as_ref(), wallet.standing_order_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub standing_order: Account<'info, StandingOrder>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteStandingOrder<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet)]
+    pub standing_order: Account<'info, StandingOrder>,
+    #[account(mut, seeds = [b"vault", wallet.key().as_ref()], bump = wallet.vault_bump)]
+    pub vault: SystemAccount<'info>,
+    /// CHECK: Spending limit PDA for the order's mint; only used if it has been initialized.
+    #[account(
+        mut,
+        seeds = [
+            b"spending_limit",
+            wallet.key().as_ref(),
+            standing_order.token_mint.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub spending_limit: UncheckedAccount<'info>,
+    /// CHECK: Address book entry PDA for the recipient; only read if it has been initialized.
+    #[account(
+        seeds = [b"address_book", wallet.key().as_ref(), standing_order.recipient.as_ref()],
+        bump
+    )]
+    pub address_book_entry: UncheckedAccount<'info>,
+    /// CHECK: Receives lamports only; checked against `standing_order.recipient`.
+    #[account(mut)]
+    pub recipient: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVestingStream<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + 32 + 8 + 32 + 32 + 33 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 9 + 1,
+        seeds = [b"vesting_stream", wallet.key().as_ref(), wallet.vesting_stream_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub vesting_stream: Account<'info, VestingStream>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVesting<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet, has_one = beneficiary)]
+    pub vesting_stream: Account<'info, VestingStream>,
+    #[account(mut, seeds = [b"vault", wallet.key().as_ref()], bump = wallet.vault_bump)]
+    pub vault: SystemAccount<'info>,
+    /// CHECK: Address book entry PDA for the beneficiary; only read if it has been initialized.
+    #[account(
+        seeds = [b"address_book", wallet.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub address_book_entry: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub beneficiary_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct AddAddressBookEntry<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init_if_needed,
+        payer = proposer,
+        space = 8 + 32 + 32 + 4 + MAX_LABEL_LEN + 9 + 1,
+        seeds = [b"address_book", wallet.key().as_ref(), address.as_ref()],
+        bump
+    )]
+    pub address_book_entry: Account<'info, AddressBookEntry>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAllowance<'info> {
+    #[account(mut)]
+    pub wallet: Account<'info, Wallet>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + 32 + 8 + 32 + 32 + 33 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 1,
+        seeds = [b"allowance", wallet.key().as_ref(), wallet.allowance_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub allowance: Account<'info, Allowance>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(destination: Pubkey)]
+pub struct SpendAllowance<'info> {
+    pub wallet: Account<'info, Wallet>,
+    #[account(mut, has_one = wallet, has_one = grantee)]
+    pub allowance: Account<'info, Allowance>,
+    #[account(mut, seeds = [b"vault", wallet.key().as_ref()], bump = wallet.vault_bump)]
+    pub vault: SystemAccount<'info>,
+    /// CHECK: Spending limit PDA for the allowance's mint; only used if it has been initialized.
+    #[account(
+        mut,
+        seeds = [
+            b"spending_limit",
+            wallet.key().as_ref(),
+            allowance.token_mint.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub spending_limit: UncheckedAccount<'info>,
+    /// CHECK: Address book entry PDA for `destination`; only read if it has been initialized.
+    #[account(
+        seeds = [b"address_book", wallet.key().as_ref(), destination.as_ref()],
+        bump
+    )]
+    pub address_book_entry: UncheckedAccount<'info>,
+    pub grantee: Signer<'info>,
+    /// CHECK: Receives lamports only; checked against `destination`.
+    #[account(mut)]
+    pub recipient: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+// Events
+#[event]
+pub struct WalletInitialized {
+    pub wallet: Pubkey,
+    pub authority: Pubkey,
+    pub index: u64,
+    pub owners: Vec<Member>,
+    pub threshold: u16,
+    pub time_lock_duration: i64,
+}
+
+#[event]
+pub struct TransactionCreated {
+    pub transaction: Pubkey,
+    pub wallet: Pubkey,
+    pub proposer: Pubkey,
+    pub index: u64,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub token_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct BatchTransactionCreated {
+    pub transaction: Pubkey,
+    pub wallet: Pubkey,
+    pub proposer: Pubkey,
+    pub index: u64,
+    pub action_count: u16,
+}
+
+#[event]
+pub struct TransactionConfirmed {
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub confirmed_weight: u16,
+}
+
+/// Marks an execution that skipped the time lock on unanimous consent.
+#[event]
+pub struct TimeLockOverridden {
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+}
+
+#[event]
+pub struct TransactionRejected {
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub rejected_weight: u16,
+}
+
+#[event]
+pub struct TransactionCancelled {
+    pub transaction: Pubkey,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct TransactionClosed {
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct TransactionExecuted {
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+}
+
+#[event]
+pub struct ConfigTransactionCreated {
+    pub transaction: Pubkey,
+    pub wallet: Pubkey,
+    pub proposer: Pubkey,
+    pub index: u64,
+    pub action: ConfigAction,
+}
+
+#[event]
+pub struct OwnerAdded {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub permissions: u8,
+    pub weight: u8,
+}
+
+#[event]
+pub struct OwnerRemoved {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct PermissionsChanged {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub permissions: u8,
+}
+
+#[event]
+pub struct WeightChanged {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub weight: u8,
+}
+
+#[event]
+pub struct UnpausePolicySet {
+    pub wallet: Pubkey,
+    pub quorum: u16,
+    pub cooldown: i64,
+}
+
+#[event]
+pub struct ThresholdChanged {
+    pub wallet: Pubkey,
+    pub threshold: u16,
+}
+
+#[event]
+pub struct GuardianAdded {
+    pub wallet: Pubkey,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct GuardianRemoved {
+    pub wallet: Pubkey,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct ProposalExpirySet {
+    pub wallet: Pubkey,
+    pub expiry: Option<i64>,
+}
+
+#[event]
+pub struct SpendingLimitSet {
+    pub wallet: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub daily_limit: u64,
+    pub monthly_limit: u64,
+}
+
+#[event]
+pub struct AmountPolicySet {
+    pub wallet: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub tiers: Vec<PolicyTier>,
+}
+
+#[event]
+pub struct StandingOrderCreated {
+    pub wallet: Pubkey,
+    pub standing_order: Pubkey,
+    pub order_id: u64,
+    pub recipient: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub amount: u64,
+    pub period: i64,
+}
+
+#[event]
+pub struct StandingOrderApproved {
+    pub wallet: Pubkey,
+    pub standing_order: Pubkey,
+}
+
+#[event]
+pub struct StandingOrderCancelled {
+    pub wallet: Pubkey,
+    pub standing_order: Pubkey,
+}
+
+#[event]
+pub struct StandingOrderExecuted {
+    pub standing_order: Pubkey,
+    pub occurrence: u32,
+    pub amount: u64,
+}
+
+#[event]
+pub struct VestingStreamCreated {
+    pub wallet: Pubkey,
+    pub vesting_stream: Pubkey,
+    pub stream_id: u64,
+    pub beneficiary: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct VestingStreamApproved {
+    pub wallet: Pubkey,
+    pub vesting_stream: Pubkey,
+}
+
+#[event]
+pub struct VestingStreamRevoked {
+    pub wallet: Pubkey,
+    pub vesting_stream: Pubkey,
+    pub revoked_at: i64,
+}
+
+/// Emitted alongside `TransactionExecuted`, whose `transaction` is the
+/// vesting stream.
+#[event]
+pub struct VestingClaimed {
+    pub vesting_stream: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    /// Total claimed from the stream so far.
+    pub claimed_amount: u64,
+}
+
+#[event]
+pub struct AddressBookEntryAdded {
+    pub wallet: Pubkey,
+    pub entry: Pubkey,
+    pub address: Pubkey,
+    pub label: String,
+}
+
+#[event]
+pub struct AddressBookEntryApproved {
+    pub wallet: Pubkey,
+    pub entry: Pubkey,
+    pub active_at: i64,
+}
+
+#[event]
+pub struct AddressBookEntryRemoved {
+    pub wallet: Pubkey,
+    pub entry: Pubkey,
+}
+
+#[event]
+pub struct DestinationPolicySet {
+    pub wallet: Pubkey,
+    pub policy: DestinationPolicy,
+    pub delay: i64,
+}
+
+#[event]
+pub struct AllowanceCreated {
+    pub wallet: Pubkey,
+    pub allowance: Pubkey,
+    pub allowance_id: u64,
+    pub grantee: Pubkey,
+    pub token_mint: Option<Pubkey>,
+    pub amount: u64,
+    pub period: i64,
+}
+
+#[event]
+pub struct AllowanceApproved {
+    pub wallet: Pubkey,
+    pub allowance: Pubkey,
+}
+
+#[event]
+pub struct AllowanceRevoked {
+    pub wallet: Pubkey,
+    pub allowance: Pubkey,
+}
+
+#[event]
+pub struct AllowanceSpent {
+    pub allowance: Pubkey,
+    pub grantee: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    /// Total spent in the current period, transfer fees included.
+    pub spent_in_period: u64,
+}
+
+#[event]
+pub struct SocialRecoveryInitiated {
+    pub wallet: Pubkey,
+    pub recovery: Pubkey,
+    pub new_owner: Pubkey,
+    pub recovery_id: u64,
+}
+
+#[event]
+pub struct SocialRecoveryExecuted {
+    pub wallet: Pubkey,
+    pub recovery: Pubkey,
+    pub recovery_id: u64,
+}
+
+#[event]
+pub struct SocialRecoveryCancelled {
+    pub wallet: Pubkey,
+    pub recovery: Pubkey,
+    pub recovery_id: u64,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct WalletPaused {
+    pub wallet: Pubkey,
+    pub paused_by: Pubkey,
+    pub reason: String,
+}
+
+#[event]
+pub struct UnpauseVoted {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub votes: u16,
+}
+
+#[event]
+pub struct WalletUnpaused {
+    pub wallet: Pubkey,
+}
 
 // Error codes
 #[error_code]
@@ -264,4 +4156,120 @@ pub enum ErrorCode {
     TimeLockNotExpired,
     #[msg("Wallet is paused")]
     WalletPaused,
-} 
\ No newline at end of file
+    #[msg("Instruction cannot target the multisig program")]
+    InvalidInstruction,
+    #[msg("Missing account required for the transfer")]
+    MissingTransferAccount,
+    #[msg("Destination does not match the transaction")]
+    InvalidDestination,
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    #[msg("Too many owners")]
+    TooManyOwners,
+    #[msg("Owner already exists")]
+    OwnerAlreadyExists,
+    #[msg("Owner not found")]
+    OwnerNotFound,
+    #[msg("Cannot remove last owner")]
+    CannotRemoveLastOwner,
+    #[msg("Wrong execute instruction for this transaction")]
+    InvalidTransactionType,
+    #[msg("Not a guardian")]
+    NotGuardian,
+    #[msg("Too many guardians")]
+    TooManyGuardians,
+    #[msg("Guardian already exists")]
+    GuardianAlreadyExists,
+    #[msg("Guardian not found")]
+    GuardianNotFound,
+    #[msg("Recovery already executed")]
+    RecoveryExecuted,
+    #[msg("Recovery was cancelled")]
+    RecoveryCancelled,
+    #[msg("Transaction was cancelled")]
+    TransactionCancelled,
+    #[msg("Already rejected")]
+    AlreadyRejected,
+    #[msg("Only the proposer can cancel")]
+    NotProposer,
+    #[msg("Transaction already confirmed by another owner")]
+    TransactionAlreadyConfirmed,
+    #[msg("Transaction has expired")]
+    TransactionExpired,
+    #[msg("Invalid expiry")]
+    InvalidExpiry,
+    #[msg("Transaction cannot be closed yet")]
+    TransactionNotClosable,
+    #[msg("Missing spending limit account")]
+    MissingSpendingLimit,
+    #[msg("Batch has no actions")]
+    EmptyBatch,
+    #[msg("Signer lacks the required permission")]
+    MissingPermission,
+    #[msg("Invalid permissions")]
+    InvalidPermissions,
+    #[msg("Member weight must be positive")]
+    InvalidWeight,
+    #[msg("Amount policy account not provided")]
+    MissingAmountPolicy,
+    #[msg("Invalid amount policy")]
+    InvalidPolicy,
+    #[msg("Wallet is not paused")]
+    WalletNotPaused,
+    #[msg("Pause reason too long")]
+    PauseReasonTooLong,
+    #[msg("Each transaction must be followed by its proposer")]
+    UnpairedCloseAccount,
+    #[msg("Transaction predates the current owner set or threshold")]
+    StaleTransaction,
+    #[msg("No owner signatures over the confirmation message")]
+    MissingSignatures,
+    #[msg("Malformed Ed25519 signature instruction")]
+    InvalidSignatureInstruction,
+    #[msg("Transfer fee cannot be covered")]
+    InvalidTransferFee,
+    #[msg("Account named by the config action not provided")]
+    MissingConfigAccount,
+    #[msg("Invalid standing order")]
+    InvalidStandingOrder,
+    #[msg("Standing order is not approved")]
+    StandingOrderNotApproved,
+    #[msg("Standing order already approved")]
+    StandingOrderAlreadyApproved,
+    #[msg("Standing order cancelled")]
+    StandingOrderCancelled,
+    #[msg("Standing order has paid its last installment")]
+    StandingOrderFinished,
+    #[msg("Next installment is not due yet")]
+    InstallmentNotDue,
+    #[msg("Payment exceeds the spending limit")]
+    SpendingLimitExceeded,
+    #[msg("Invalid vesting stream")]
+    InvalidVestingStream,
+    #[msg("Vesting stream is not approved")]
+    VestingStreamNotApproved,
+    #[msg("Vesting stream already approved")]
+    VestingStreamAlreadyApproved,
+    #[msg("Vesting stream cannot be revoked")]
+    VestingStreamNotRevocable,
+    #[msg("Vesting stream already revoked")]
+    VestingStreamRevoked,
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+    #[msg("Label too long")]
+    LabelTooLong,
+    #[msg("Address is already listed")]
+    AddressAlreadyListed,
+    #[msg("Destination is not in the address book")]
+    DestinationNotListed,
+    #[msg("Invalid allowance")]
+    InvalidAllowance,
+    #[msg("Allowance not approved")]
+    AllowanceNotApproved,
+    #[msg("Allowance already approved")]
+    AllowanceAlreadyApproved,
+    #[msg("Allowance revoked")]
+    AllowanceRevoked,
+    #[msg("Allowance exceeded for this period")]
+    AllowanceExceeded,
+}
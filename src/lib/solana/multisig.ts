@@ -0,0 +1,114 @@
+import { BN } from '@coral-xyz/anchor'
+import { PublicKey } from '@solana/web3.js'
+
+export const MULTISIG_PROGRAM_ID = new PublicKey('MSigWa11etProgram11111111111111111111111111')
+
+type Index = number | bigint | BN
+
+const u64 = (value: Index) => new BN(value.toString()).toArrayLike(Buffer, 'le', 8)
+
+/**
+ * Predict the address of the `index`-th wallet created by `authority`,
+ * mirroring `WalletFactory.predictWalletAddress` on the EVM side.
+ */
+export function predictWalletAddress(
+  authority: PublicKey,
+  index: Index,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('wallet'), authority.toBuffer(), u64(index)],
+    programId
+  )
+}
+
+/**
+ * Address of the PDA that holds a wallet's funds and signs its instructions.
+ */
+export function findVaultAddress(
+  wallet: PublicKey,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from('vault'), wallet.toBuffer()], programId)
+}
+
+/**
+ * Address of the wallet's `index`-th proposal.
+ */
+export function findTransactionAddress(
+  wallet: PublicKey,
+  index: Index,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('transaction'), wallet.toBuffer(), u64(index)],
+    programId
+  )
+}
+
+/**
+ * Message an owner signs off-chain to confirm a proposal through
+ * `confirmTransactionWithSignatures`. `voteNonce` is the proposal's current
+ * `voteNonce`; every rejection bumps it and voids older signatures.
+ */
+export function confirmationMessage(transaction: PublicKey, voteNonce: number): Buffer {
+  const nonce = Buffer.alloc(4)
+  nonce.writeUInt32LE(voteNonce)
+  return Buffer.concat([Buffer.from('multisig-wallet:confirm:'), transaction.toBuffer(), nonce])
+}
+
+/**
+ * Address of the wallet's `index`-th standing order.
+ */
+export function findStandingOrderAddress(
+  wallet: PublicKey,
+  index: Index,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('standing_order'), wallet.toBuffer(), u64(index)],
+    programId
+  )
+}
+
+/**
+ * Address of the wallet's `index`-th vesting stream.
+ */
+export function findVestingStreamAddress(
+  wallet: PublicKey,
+  index: Index,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('vesting_stream'), wallet.toBuffer(), u64(index)],
+    programId
+  )
+}
+
+/**
+ * Address of the wallet's address book entry for `address`.
+ */
+export function findAddressBookEntryAddress(
+  wallet: PublicKey,
+  address: PublicKey,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('address_book'), wallet.toBuffer(), address.toBuffer()],
+    programId
+  )
+}
+
+/**
+ * Address of the wallet's `index`-th spending allowance.
+ */
+export function findAllowanceAddress(
+  wallet: PublicKey,
+  index: Index,
+  programId: PublicKey = MULTISIG_PROGRAM_ID
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from('allowance'), wallet.toBuffer(), u64(index)],
+    programId
+  )
+}
@@ -0,0 +1,1547 @@
+import * as anchor from '@coral-xyz/anchor'
+import { BN, Program } from '@coral-xyz/anchor'
+import {
+  Ed25519Program,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+} from '@solana/web3.js'
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  ExtensionType,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+  mintTo,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+} from '@solana/spl-token'
+import { expect } from 'chai'
+import { MultisigWallet } from '../target/types/multisig_wallet'
+import {
+  confirmationMessage,
+  findAddressBookEntryAddress,
+  findAllowanceAddress,
+  findStandingOrderAddress,
+  findTransactionAddress,
+  findVaultAddress,
+  findVestingStreamAddress,
+  predictWalletAddress,
+} from '../src/lib/solana/multisig'
+
+describe('multisig-wallet', () => {
+  const provider = anchor.AnchorProvider.env()
+  anchor.setProvider(provider)
+
+  const program = anchor.workspace.MultisigWallet as Program<MultisigWallet>
+  const payer = (provider.wallet as anchor.Wallet).payer
+  const secondOwner = Keypair.generate()
+
+  const [wallet, walletBump] = predictWalletAddress(
+    provider.wallet.publicKey,
+    0,
+    program.programId
+  )
+  const [vault] = findVaultAddress(wallet, program.programId)
+
+  const spendingLimitFor = (mint: PublicKey | null) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from('spending_limit'), wallet.toBuffer(), (mint ?? PublicKey.default).toBuffer()],
+      program.programId
+    )[0]
+
+  const amountPolicyFor = (mint: PublicKey | null) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from('amount_policy'), wallet.toBuffer(), (mint ?? PublicKey.default).toBuffer()],
+      program.programId
+    )[0]
+
+  const addressBookEntryFor = (address: PublicKey) =>
+    findAddressBookEntryAddress(wallet, address, program.programId)[0]
+
+  const propose = async (
+    destination: PublicKey,
+    amount: number,
+    tokenMint: PublicKey | null = null,
+    expiresAt: BN | null = null
+  ) => {
+    const { nonce } = await program.account.wallet.fetch(wallet)
+    const [transaction] = findTransactionAddress(wallet, nonce, program.programId)
+    await program.methods
+      .createTransaction(destination, new BN(amount), null, tokenMint, expiresAt)
+      .accounts({
+        wallet,
+        transaction,
+        spendingLimit: spendingLimitFor(tokenMint),
+        amountPolicy: amountPolicyFor(tokenMint),
+        addressBookEntry: addressBookEntryFor(destination),
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    return transaction
+  }
+
+  // Proposes `ix` for the vault to sign through `invoke_signed`.
+  const proposeInstruction = async (ix: anchor.web3.TransactionInstruction) => {
+    const { nonce } = await program.account.wallet.fetch(wallet)
+    const [transaction] = findTransactionAddress(wallet, nonce, program.programId)
+    await program.methods
+      .createTransaction(
+        vault,
+        new BN(0),
+        { programId: ix.programId, accounts: ix.keys, data: ix.data },
+        null,
+        null
+      )
+      .accounts({
+        wallet,
+        transaction,
+        spendingLimit: spendingLimitFor(null),
+        amountPolicy: amountPolicyFor(null),
+        addressBookEntry: addressBookEntryFor(vault),
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    return transaction
+  }
+
+  const confirm = async (transaction: PublicKey) => {
+    await program.methods
+      .confirmTransaction()
+      .accounts({ wallet, transaction, owner: secondOwner.publicKey })
+      .signers([secondOwner])
+      .rpc()
+  }
+
+  // Proposes, confirms and executes a config action that names `target`.
+  const applyConfig = async (action: any, target: PublicKey, executor: Keypair = payer) => {
+    const { nonce } = await program.account.wallet.fetch(wallet)
+    const [transaction] = findTransactionAddress(wallet, nonce, program.programId)
+    await program.methods
+      .createConfigTransaction(action, null)
+      .accounts({
+        wallet,
+        transaction,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    await confirm(transaction)
+    await program.methods
+      .executeConfigTransaction()
+      .accounts({
+        wallet,
+        transaction,
+        executor: executor.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([{ pubkey: target, isSigner: false, isWritable: true }])
+      .signers([executor])
+      .rpc()
+  }
+
+  before(async () => {
+    await program.methods
+      .initializeWallet(
+        [
+          { key: provider.wallet.publicKey, permissions: 7, weight: 1 },
+          { key: secondOwner.publicKey, permissions: 7, weight: 1 },
+        ],
+        2,
+        new BN(0),
+        walletBump,
+        new BN(0)
+      )
+      .accounts({
+        wallet,
+        authority: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: vault,
+          lamports: 2 * LAMPORTS_PER_SOL,
+        })
+      )
+    )
+  })
+
+  it('transfers SOL from the vault once the threshold is met', async () => {
+    const recipient = Keypair.generate().publicKey
+    const transaction = await propose(recipient, LAMPORTS_PER_SOL)
+
+    const execute = () =>
+      program.methods
+        .executeTransaction()
+        .accounts({
+          wallet,
+          transaction,
+          vault,
+          spendingLimit: spendingLimitFor(null),
+          addressBookEntry: addressBookEntryFor(recipient),
+          destination: recipient,
+          vaultTokenAccount: null,
+          destinationTokenAccount: null,
+          mint: null,
+          executor: provider.wallet.publicKey,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+
+    try {
+      await execute()
+      expect.fail('executed without enough confirmations')
+    } catch (err) {
+      expect(String(err)).to.include('InsufficientConfirmations')
+    }
+
+    await confirm(transaction)
+    await execute()
+
+    expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL)
+    const account = await program.account.transaction.fetch(transaction)
+    expect(account.isExecuted).to.equal(true)
+  })
+
+  it('transfers SPL tokens from a vault-owned token account', async () => {
+    const recipient = Keypair.generate().publicKey
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 6)
+    const vaultTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint,
+      vault,
+      Keypair.generate()
+    )
+    const destinationTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint,
+      recipient,
+      Keypair.generate()
+    )
+    await mintTo(provider.connection, payer, mint, vaultTokenAccount, payer, 1_000_000)
+
+    const transaction = await propose(recipient, 400_000, mint)
+    await confirm(transaction)
+
+    await program.methods
+      .executeTransaction()
+      .accounts({
+        wallet,
+        transaction,
+        vault,
+        spendingLimit: spendingLimitFor(mint),
+        addressBookEntry: addressBookEntryFor(recipient),
+        destination: null,
+        vaultTokenAccount,
+        destinationTokenAccount,
+        mint,
+        executor: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    expect(Number((await getAccount(provider.connection, vaultTokenAccount)).amount)).to.equal(
+      600_000
+    )
+    expect(
+      Number((await getAccount(provider.connection, destinationTokenAccount)).amount)
+    ).to.equal(400_000)
+  })
+
+  it("creates the destination's associated token account when missing", async () => {
+    const recipient = Keypair.generate().publicKey
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 6)
+    const vaultTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint,
+      vault,
+      Keypair.generate()
+    )
+    await mintTo(provider.connection, payer, mint, vaultTokenAccount, payer, 1_000)
+    const destinationTokenAccount = getAssociatedTokenAddressSync(mint, recipient)
+
+    const transaction = await propose(recipient, 250, mint)
+    await confirm(transaction)
+
+    await program.methods
+      .executeTransaction()
+      .accounts({
+        wallet,
+        transaction,
+        vault,
+        spendingLimit: spendingLimitFor(mint),
+        addressBookEntry: addressBookEntryFor(recipient),
+        destination: recipient,
+        vaultTokenAccount,
+        destinationTokenAccount,
+        mint,
+        executor: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    expect(
+      Number((await getAccount(provider.connection, destinationTokenAccount)).amount)
+    ).to.equal(250)
+  })
+
+  it('tops up Token-2022 transfer fees and counts them against the limit', async () => {
+    const recipient = Keypair.generate().publicKey
+    const mint = Keypair.generate()
+    const space = getMintLen([ExtensionType.TransferFeeConfig])
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: payer.publicKey,
+          newAccountPubkey: mint.publicKey,
+          space,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(space),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(
+          mint.publicKey,
+          payer.publicKey,
+          payer.publicKey,
+          100,
+          BigInt(1_000_000),
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(
+          mint.publicKey,
+          6,
+          payer.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [mint]
+    )
+    const vaultTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint.publicKey,
+      vault,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    )
+    const destinationTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint.publicKey,
+      recipient,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    )
+    await mintTo(
+      provider.connection,
+      payer,
+      mint.publicKey,
+      vaultTokenAccount,
+      payer,
+      100_000,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    )
+    await applyConfig(
+      {
+        setSpendingLimit: {
+          tokenMint: mint.publicKey,
+          dailyLimit: new BN(50_000),
+          monthlyLimit: new BN(500_000),
+        },
+      },
+      spendingLimitFor(mint.publicKey)
+    )
+
+    const transaction = await propose(recipient, 10_000, mint.publicKey)
+    await confirm(transaction)
+
+    await program.methods
+      .executeTransaction()
+      .accounts({
+        wallet,
+        transaction,
+        vault,
+        spendingLimit: spendingLimitFor(mint.publicKey),
+        addressBookEntry: addressBookEntryFor(recipient),
+        destination: null,
+        vaultTokenAccount,
+        destinationTokenAccount,
+        mint: mint.publicKey,
+        executor: provider.wallet.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    const received = await getAccount(
+      provider.connection,
+      destinationTokenAccount,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    )
+    const remaining = await getAccount(
+      provider.connection,
+      vaultTokenAccount,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    )
+    const { dailySpent } = await program.account.spendingLimit.fetch(
+      spendingLimitFor(mint.publicKey)
+    )
+
+    // 1% of the gross amount is withheld, so the vault sends 10,102.
+    expect(Number(received.amount)).to.equal(10_000)
+    expect(Number(remaining.amount)).to.equal(100_000 - 10_102)
+    expect(dailySpent.toNumber()).to.equal(10_102)
+  })
+
+  it('escalates transfers over the spending limit to every owner', async () => {
+    const recipient = Keypair.generate().publicKey
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 6)
+
+    await applyConfig(
+      {
+        setSpendingLimit: { tokenMint: mint, dailyLimit: new BN(100), monthlyLimit: new BN(1_000) },
+      },
+      spendingLimitFor(mint)
+    )
+
+    const withinLimit = await propose(recipient, 100, mint)
+    const overLimit = await propose(recipient, 101, mint)
+
+    expect((await program.account.transaction.fetch(withinLimit)).requiresAllConfirmations).to
+      .be.false
+    expect((await program.account.transaction.fetch(overLimit)).requiresAllConfirmations).to.be
+      .true
+
+    // Instructions can spend from the vault unseen, so any limit escalates them.
+    const instruction = await proposeInstruction(
+      SystemProgram.transfer({ fromPubkey: vault, toPubkey: recipient, lamports: 1 })
+    )
+    expect((await program.account.transaction.fetch(instruction)).requiresAllConfirmations).to.be
+      .true
+  })
+
+  it('snapshots the amount tier onto new proposals', async () => {
+    const recipient = Keypair.generate().publicKey
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 6)
+
+    await applyConfig(
+      {
+        setAmountPolicy: {
+          tokenMint: mint,
+          tiers: [
+            { minAmount: new BN(0), threshold: 1, timeLockDuration: new BN(0) },
+            { minAmount: new BN(1_000), threshold: 2, timeLockDuration: new BN(86_400) },
+          ],
+        },
+      },
+      amountPolicyFor(mint)
+    )
+
+    const small = await program.account.transaction.fetch(await propose(recipient, 999, mint))
+    const large = await program.account.transaction.fetch(await propose(recipient, 1_000, mint))
+
+    expect(small.threshold).to.equal(2)
+    expect(small.timeLockDuration.toNumber()).to.equal(0)
+    expect(large.threshold).to.equal(2)
+    expect(large.timeLockDuration.toNumber()).to.equal(86_400)
+
+    // Instructions can move any amount, so once tiers exist they need every owner.
+    const instruction = await proposeInstruction(
+      SystemProgram.transfer({ fromPubkey: vault, toPubkey: recipient, lamports: 1 })
+    )
+    expect((await program.account.transaction.fetch(instruction)).requiresAllConfirmations).to.be
+      .true
+  })
+
+  it('closes cancelled proposals in bulk and refunds the proposer', async () => {
+    const recipient = Keypair.generate().publicKey
+    const transactions = [await propose(recipient, 1), await propose(recipient, 2)]
+    for (const transaction of transactions) {
+      await program.methods
+        .cancelTransaction()
+        .accounts({ wallet, transaction, proposer: provider.wallet.publicKey })
+        .rpc()
+    }
+
+    const closeAll = (pending: PublicKey[]) =>
+      program.methods
+        .closeTransactions()
+        .accounts({ wallet })
+        .remainingAccounts(
+          pending.flatMap((transaction) => [
+            { pubkey: transaction, isSigner: false, isWritable: true },
+            { pubkey: provider.wallet.publicKey, isSigner: false, isWritable: true },
+          ])
+        )
+        .rpc()
+
+    await closeAll(transactions)
+    for (const transaction of transactions) {
+      expect(await provider.connection.getAccountInfo(transaction)).to.be.null
+    }
+
+    // A second crank skips what is already closed or still open.
+    const open = await propose(recipient, 3)
+    await closeAll([...transactions, open])
+    expect(await provider.connection.getAccountInfo(open)).to.not.be.null
+  })
+
+  it('closes a single cancelled proposal back to its proposer', async () => {
+    const transaction = await propose(Keypair.generate().publicKey, 1)
+    await program.methods
+      .cancelTransaction()
+      .accounts({ wallet, transaction, proposer: provider.wallet.publicKey })
+      .rpc()
+
+    try {
+      await program.methods
+        .closeTransaction()
+        .accounts({ wallet, transaction, proposer: secondOwner.publicKey })
+        .rpc()
+      expect.fail('sent the rent to someone other than the proposer')
+    } catch (err) {
+      expect(String(err)).to.include('ConstraintHasOne')
+    }
+
+    await program.methods
+      .closeTransaction()
+      .accounts({ wallet, transaction, proposer: provider.wallet.publicKey })
+      .rpc()
+    expect(await provider.connection.getAccountInfo(transaction)).to.be.null
+  })
+
+  it('confirms with owner signatures collected off-chain', async () => {
+    const transaction = await propose(Keypair.generate().publicKey, 1)
+
+    await program.methods
+      .confirmTransactionWithSignatures()
+      .accounts({ wallet, transaction, instructions: SYSVAR_INSTRUCTIONS_PUBKEY })
+      .preInstructions([
+        Ed25519Program.createInstructionWithPrivateKey({
+          privateKey: secondOwner.secretKey,
+          message: confirmationMessage(transaction, 0),
+        }),
+      ])
+      .rpc()
+
+    const { confirmations } = await program.account.transaction.fetch(transaction)
+    expect(confirmations.map((key) => key.toBase58())).to.include(
+      secondOwner.publicKey.toBase58()
+    )
+  })
+
+  it('refuses to turn a rejection back into a confirmation with a signature', async () => {
+    // A third voter keeps one rejection from cancelling the proposal outright.
+    const thirdOwner = Keypair.generate().publicKey
+    await applyConfig({ addOwner: { owner: thirdOwner, permissions: 2, weight: 1 } }, wallet)
+    const transaction = await propose(Keypair.generate().publicKey, 1)
+
+    const confirmWithSignature = (voteNonce: number) =>
+      program.methods
+        .confirmTransactionWithSignatures()
+        .accounts({ wallet, transaction, instructions: SYSVAR_INSTRUCTIONS_PUBKEY })
+        .preInstructions([
+          Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: secondOwner.secretKey,
+            message: confirmationMessage(transaction, voteNonce),
+          }),
+        ])
+        .rpc()
+
+    await confirmWithSignature(0)
+    await program.methods
+      .rejectTransaction()
+      .accounts({ wallet, transaction, owner: secondOwner.publicKey })
+      .signers([secondOwner])
+      .rpc()
+
+    // The signature from the first confirmation is public once it lands.
+    try {
+      await confirmWithSignature(0)
+      expect.fail('replayed a signature over a rejection')
+    } catch (err) {
+      expect(String(err)).to.include('MissingSignatures')
+    }
+    try {
+      await confirmWithSignature(1)
+      expect.fail('overrode a rejection with a signature')
+    } catch (err) {
+      expect(String(err)).to.include('AlreadyRejected')
+    }
+
+    const { confirmations, rejections } = await program.account.transaction.fetch(transaction)
+    expect(confirmations.map((key) => key.toBase58())).to.not.include(
+      secondOwner.publicKey.toBase58()
+    )
+    expect(rejections.map((key) => key.toBase58())).to.include(secondOwner.publicKey.toBase58())
+
+    await applyConfig({ removeOwner: { owner: thirdOwner } }, wallet)
+  })
+
+  it('pays an approved standing order from the crank', async () => {
+    const recipient = Keypair.generate().publicKey
+    const { standingOrderCount } = await program.account.wallet.fetch(wallet)
+    const [standingOrder] = findStandingOrderAddress(wallet, standingOrderCount, program.programId)
+
+    await program.methods
+      .createStandingOrder(
+        recipient,
+        null,
+        new BN(LAMPORTS_PER_SOL / 100),
+        new BN(86_400),
+        new BN(0),
+        null,
+        1
+      )
+      .accounts({
+        wallet,
+        standingOrder,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    await applyConfig({ approveStandingOrder: { standingOrder } }, standingOrder)
+
+    await program.methods
+      .executeStandingOrder()
+      .accounts({
+        wallet,
+        standingOrder,
+        vault,
+        spendingLimit: spendingLimitFor(null),
+        addressBookEntry: addressBookEntryFor(recipient),
+        recipient,
+        vaultTokenAccount: null,
+        recipientTokenAccount: null,
+        mint: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL / 100)
+    expect((await program.account.standingOrder.fetch(standingOrder)).occurrencesPaid).to.equal(1)
+  })
+
+  it('lets the beneficiary claim a vested stream', async () => {
+    const beneficiary = Keypair.generate()
+    const { vestingStreamCount } = await program.account.wallet.fetch(wallet)
+    const [vestingStream] = findVestingStreamAddress(wallet, vestingStreamCount, program.programId)
+
+    await program.methods
+      .createVestingStream(
+        beneficiary.publicKey,
+        null,
+        new BN(LAMPORTS_PER_SOL / 100),
+        new BN(0),
+        new BN(0),
+        new BN(1),
+        true
+      )
+      .accounts({
+        wallet,
+        vestingStream,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    await applyConfig({ approveVestingStream: { vestingStream } }, vestingStream)
+
+    await program.methods
+      .claimVesting()
+      .accounts({
+        wallet,
+        vestingStream,
+        vault,
+        addressBookEntry: addressBookEntryFor(beneficiary.publicKey),
+        beneficiary: beneficiary.publicKey,
+        vaultTokenAccount: null,
+        beneficiaryTokenAccount: null,
+        mint: null,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([beneficiary])
+      .rpc()
+
+    expect(await provider.connection.getBalance(beneficiary.publicKey)).to.equal(
+      LAMPORTS_PER_SOL / 100
+    )
+  })
+
+  it('restricts proposals to the address book', async () => {
+    const vendor = Keypair.generate().publicKey
+    const [entry] = findAddressBookEntryAddress(wallet, vendor, program.programId)
+
+    await program.methods
+      .addAddressBookEntry(vendor, 'vendor')
+      .accounts({
+        wallet,
+        addressBookEntry: entry,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    await applyConfig({ approveAddressBookEntry: { entry } }, entry)
+    await applyConfig(
+      { setDestinationPolicy: { policy: { allowlistOnly: {} }, delay: new BN(0) } },
+      wallet
+    )
+
+    expect((await program.account.addressBookEntry.fetch(entry)).activeAt).to.not.be.null
+    const pending = await propose(vendor, LAMPORTS_PER_SOL / 100)
+    try {
+      await propose(Keypair.generate().publicKey, LAMPORTS_PER_SOL / 100)
+      expect.fail('unlisted destination was accepted')
+    } catch (err) {
+      expect(String(err)).to.include('DestinationNotListed')
+    }
+
+    // The vendor's entry also covers batch payments to its token accounts.
+    const mint = await createMint(provider.connection, payer, payer.publicKey, null, 6)
+    const source = await createAccount(provider.connection, payer, mint, vault, Keypair.generate())
+    const vendorTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      mint,
+      vendor,
+      Keypair.generate()
+    )
+    await mintTo(provider.connection, payer, mint, source, payer, 100)
+    const { nonce } = await program.account.wallet.fetch(wallet)
+    const [batch] = findTransactionAddress(wallet, nonce, program.programId)
+    await program.methods
+      .createBatchTransaction(
+        [
+          {
+            tokenTransfer: {
+              tokenMint: mint,
+              source,
+              destination: vendorTokenAccount,
+              amount: new BN(100),
+            },
+          },
+        ],
+        null
+      )
+      .accounts({
+        wallet,
+        transaction: batch,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        [spendingLimitFor(mint), amountPolicyFor(mint), entry, vendorTokenAccount].map(
+          (pubkey) => ({ pubkey, isSigner: false, isWritable: false })
+        )
+      )
+      .rpc()
+    expect((await program.account.transaction.fetch(batch)).requiresAllConfirmations).to.be.false
+
+    // Removing the entry stops proposals that were approved while it was listed.
+    await confirm(pending)
+    await applyConfig({ removeAddressBookEntry: { entry } }, entry)
+    try {
+      await program.methods
+        .executeTransaction()
+        .accounts({
+          wallet,
+          transaction: pending,
+          vault,
+          spendingLimit: spendingLimitFor(null),
+          addressBookEntry: entry,
+          destination: vendor,
+          vaultTokenAccount: null,
+          destinationTokenAccount: null,
+          mint: null,
+          executor: provider.wallet.publicKey,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+      expect.fail('paid a removed address book entry')
+    } catch (err) {
+      expect(String(err)).to.include('DestinationNotListed')
+    }
+
+    await applyConfig({ setDestinationPolicy: { policy: { open: {} }, delay: new BN(0) } }, wallet)
+  })
+
+  it('lets a grantee spend an allowance alone', async () => {
+    const grantee = Keypair.generate()
+    const recipient = Keypair.generate().publicKey
+    const { allowanceCount } = await program.account.wallet.fetch(wallet)
+    const [allowance] = findAllowanceAddress(wallet, allowanceCount, program.programId)
+
+    await program.methods
+      .createAllowance(
+        grantee.publicKey,
+        null,
+        new BN(LAMPORTS_PER_SOL / 100),
+        new BN(604_800),
+        false
+      )
+      .accounts({
+        wallet,
+        allowance,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    await applyConfig({ approveAllowance: { allowance } }, allowance)
+
+    const spend = (amount: number) =>
+      program.methods
+        .spendAllowance(recipient, new BN(amount))
+        .accounts({
+          wallet,
+          allowance,
+          vault,
+          spendingLimit: spendingLimitFor(null),
+          addressBookEntry: addressBookEntryFor(recipient),
+          grantee: grantee.publicKey,
+          recipient,
+          vaultTokenAccount: null,
+          recipientTokenAccount: null,
+          mint: null,
+          tokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([grantee])
+        .rpc()
+
+    await spend(LAMPORTS_PER_SOL / 100)
+    expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL / 100)
+
+    try {
+      await spend(1)
+      expect.fail('spent past the allowance')
+    } catch (err) {
+      expect(String(err)).to.include('AllowanceExceeded')
+    }
+  })
+
+  it('lets owners vote out a pauser while the wallet is paused', async () => {
+    const pauser = Keypair.generate()
+    await applyConfig({ addOwner: { owner: pauser.publicKey, permissions: 2, weight: 1 } }, wallet)
+
+    await program.methods
+      .pauseWallet('compromised')
+      .accounts({ wallet, pauser: pauser.publicKey })
+      .signers([pauser])
+      .rpc()
+    await program.methods
+      .unpauseWallet()
+      .accounts({ wallet, owner: pauser.publicKey })
+      .signers([pauser])
+      .rpc()
+    await applyConfig({ removeOwner: { owner: pauser.publicKey } }, wallet)
+
+    // The removed owner's unpause vote goes with it, so the wallet can shrink.
+    const { unpauseVotes } = await program.account.wallet.fetch(wallet)
+    expect(unpauseVotes.map((key) => key.toBase58())).to.not.include(pauser.publicKey.toBase58())
+
+    for (const owner of [payer, secondOwner]) {
+      await program.methods
+        .unpauseWallet()
+        .accounts({ wallet, owner: owner.publicKey })
+        .signers([owner])
+        .rpc()
+    }
+
+    const account = await program.account.wallet.fetch(wallet)
+    expect(account.isPaused).to.be.false
+    expect(account.owners.map((member) => member.key.toBase58())).to.not.include(
+      pauser.publicKey.toBase58()
+    )
+  })
+
+  it('executes an instruction signed by the vault', async () => {
+    const recipient = Keypair.generate().publicKey
+    const transaction = await proposeInstruction(
+      SystemProgram.transfer({
+        fromPubkey: vault,
+        toPubkey: recipient,
+        lamports: LAMPORTS_PER_SOL / 100,
+      })
+    )
+    await confirm(transaction)
+
+    await program.methods
+      .executeTransaction()
+      .accounts({
+        wallet,
+        transaction,
+        vault,
+        spendingLimit: spendingLimitFor(null),
+        addressBookEntry: addressBookEntryFor(vault),
+        destination: null,
+        vaultTokenAccount: null,
+        destinationTokenAccount: null,
+        mint: null,
+        executor: provider.wallet.publicKey,
+        tokenProgram: null,
+        associatedTokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: recipient, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ])
+      .rpc()
+
+    expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL / 100)
+  })
+
+  it('refuses instructions that call back into the wallet program', async () => {
+    const reentry = await program.methods
+      .pauseWallet('reentry')
+      .accounts({ wallet, pauser: vault })
+      .instruction()
+
+    try {
+      await proposeInstruction(reentry)
+      expect.fail('proposed an instruction targeting the wallet program')
+    } catch (err) {
+      expect(String(err)).to.include('InvalidInstruction')
+    }
+  })
+
+  it('expires proposals and lets the proposer close them', async () => {
+    const chainTime = async () =>
+      (await provider.connection.getBlockTime(await provider.connection.getSlot())) ?? 0
+
+    const expiresAt = (await chainTime()) + 2
+    const transaction = await propose(
+      Keypair.generate().publicKey,
+      LAMPORTS_PER_SOL / 100,
+      null,
+      new BN(expiresAt)
+    )
+    while ((await chainTime()) <= expiresAt) {
+      await new Promise((resolve) => setTimeout(resolve, 500))
+    }
+
+    try {
+      await confirm(transaction)
+      expect.fail('confirmed an expired proposal')
+    } catch (err) {
+      expect(String(err)).to.include('TransactionExpired')
+    }
+
+    await program.methods
+      .closeTransaction()
+      .accounts({ wallet, transaction, proposer: provider.wallet.publicKey })
+      .rpc()
+    expect(await provider.connection.getAccountInfo(transaction)).to.be.null
+  })
+
+  it('refuses a default expiry that overflows the proposal time', async () => {
+    await applyConfig({ setProposalExpiry: { expiry: new BN('9223372036854775807') } }, wallet)
+
+    try {
+      await propose(Keypair.generate().publicKey, 1)
+      expect.fail('proposed with an overflowing expiry')
+    } catch (err) {
+      expect(String(err)).to.include('InvalidExpiry')
+    }
+
+    await applyConfig({ setProposalExpiry: { expiry: null } }, wallet)
+  })
+
+  it('executes every batch action or none of them', async () => {
+    const proposeBatch = async (transfers: [PublicKey, number][]) => {
+      const { nonce } = await program.account.wallet.fetch(wallet)
+      const [batch] = findTransactionAddress(wallet, nonce, program.programId)
+      await program.methods
+        .createBatchTransaction(
+          transfers.map(([destination, amount]) => ({
+            solTransfer: { destination, amount: new BN(amount) },
+          })),
+          null
+        )
+        .accounts({
+          wallet,
+          transaction: batch,
+          proposer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          [spendingLimitFor(null), amountPolicyFor(null)].map((pubkey) => ({
+            pubkey,
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc()
+      await confirm(batch)
+      return batch
+    }
+
+    const executeBatch = (batch: PublicKey, recipients: PublicKey[]) =>
+      program.methods
+        .executeTransaction()
+        .accounts({
+          wallet,
+          transaction: batch,
+          vault,
+          spendingLimit: spendingLimitFor(null),
+          addressBookEntry: addressBookEntryFor(vault),
+          destination: null,
+          vaultTokenAccount: null,
+          destinationTokenAccount: null,
+          mint: null,
+          executor: provider.wallet.publicKey,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          recipients.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        )
+        .rpc()
+
+    const [first, second] = [Keypair.generate().publicKey, Keypair.generate().publicKey]
+    const paid = await proposeBatch([
+      [first, LAMPORTS_PER_SOL / 100],
+      [second, LAMPORTS_PER_SOL / 50],
+    ])
+    await executeBatch(paid, [first, second])
+    expect(await provider.connection.getBalance(first)).to.equal(LAMPORTS_PER_SOL / 100)
+    expect(await provider.connection.getBalance(second)).to.equal(LAMPORTS_PER_SOL / 50)
+
+    // The second transfer overdraws the vault, so the first must not land either.
+    const [third, fourth] = [Keypair.generate().publicKey, Keypair.generate().publicKey]
+    const overdrawn = await proposeBatch([
+      [third, LAMPORTS_PER_SOL / 100],
+      [fourth, await provider.connection.getBalance(vault)],
+    ])
+    try {
+      await executeBatch(overdrawn, [third, fourth])
+      expect.fail('executed a batch the vault cannot cover')
+    } catch (err) {
+      // The system program's insufficient-funds error.
+      expect(String(err)).to.include('custom program error: 0x1')
+    }
+    expect(await provider.connection.getBalance(third)).to.equal(0)
+    expect((await program.account.transaction.fetch(overdrawn)).isExecuted).to.be.false
+  })
+
+  it('holds a guardian recovery behind its time lock until an owner cancels it', async () => {
+    const guardians = [Keypair.generate(), Keypair.generate()]
+    for (const guardian of guardians) {
+      await applyConfig({ addGuardian: { guardian: guardian.publicKey } }, wallet)
+    }
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: guardians[0].publicKey,
+          lamports: LAMPORTS_PER_SOL / 10,
+        })
+      )
+    )
+
+    const { recoveryCount } = await program.account.wallet.fetch(wallet)
+    const [recovery] = PublicKey.findProgramAddressSync(
+      [Buffer.from('recovery'), wallet.toBuffer(), recoveryCount.toArrayLike(Buffer, 'le', 8)],
+      program.programId
+    )
+    await program.methods
+      .initiateSocialRecovery(secondOwner.publicKey, Keypair.generate().publicKey)
+      .accounts({
+        wallet,
+        recovery,
+        guardian: guardians[0].publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([guardians[0]])
+      .rpc()
+
+    const execute = () =>
+      program.methods.executeSocialRecovery().accounts({ wallet, recovery }).rpc()
+
+    try {
+      await execute()
+      expect.fail('recovered with a minority of guardians')
+    } catch (err) {
+      expect(String(err)).to.include('InsufficientConfirmations')
+    }
+
+    await program.methods
+      .confirmSocialRecovery()
+      .accounts({ wallet, recovery, guardian: guardians[1].publicKey })
+      .signers([guardians[1]])
+      .rpc()
+
+    // SOCIAL_RECOVERY_TIMELOCK is seven days and the local validator can't
+    // warp its clock, so only the refusal before it elapses is covered here.
+    try {
+      await execute()
+      expect.fail('recovered before the time lock elapsed')
+    } catch (err) {
+      expect(String(err)).to.include('TimeLockNotExpired')
+    }
+
+    await program.methods
+      .cancelSocialRecovery()
+      .accounts({ wallet, recovery, owner: provider.wallet.publicKey })
+      .rpc()
+
+    try {
+      await execute()
+      expect.fail('executed a cancelled recovery')
+    } catch (err) {
+      expect(String(err)).to.include('RecoveryCancelled')
+    }
+    expect((await program.account.socialRecovery.fetch(recovery)).confirmations).to.have.length(2)
+  })
+
+  it('blocks execution once a proposal is rejected out or withdrawn', async () => {
+    const recipient = Keypair.generate().publicKey
+    const execute = (transaction: PublicKey) =>
+      program.methods
+        .executeTransaction()
+        .accounts({
+          wallet,
+          transaction,
+          vault,
+          spendingLimit: spendingLimitFor(null),
+          addressBookEntry: addressBookEntryFor(recipient),
+          destination: recipient,
+          vaultTokenAccount: null,
+          destinationTokenAccount: null,
+          mint: null,
+          executor: provider.wallet.publicKey,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+
+    // With a 2-of-2 wallet one rejection leaves the threshold out of reach.
+    const rejected = await propose(recipient, LAMPORTS_PER_SOL / 100)
+    await program.methods
+      .rejectTransaction()
+      .accounts({ wallet, transaction: rejected, owner: secondOwner.publicKey })
+      .signers([secondOwner])
+      .rpc()
+    expect((await program.account.transaction.fetch(rejected)).isCancelled).to.be.true
+
+    const withdrawn = await propose(recipient, LAMPORTS_PER_SOL / 100)
+    await program.methods
+      .cancelTransaction()
+      .accounts({ wallet, transaction: withdrawn, proposer: provider.wallet.publicKey })
+      .rpc()
+    expect((await program.account.transaction.fetch(withdrawn)).isCancelled).to.be.true
+
+    for (const transaction of [rejected, withdrawn]) {
+      try {
+        await execute(transaction)
+        expect.fail('executed a cancelled proposal')
+      } catch (err) {
+        expect(String(err)).to.include('TransactionCancelled')
+      }
+    }
+    expect(await provider.connection.getBalance(recipient)).to.equal(0)
+  })
+
+  it('refuses votes on proposals made before a threshold change', async () => {
+    const transaction = await propose(Keypair.generate().publicKey, LAMPORTS_PER_SOL / 100)
+    await applyConfig({ changeThreshold: { threshold: 2 } }, wallet)
+
+    try {
+      await confirm(transaction)
+      expect.fail('confirmed a proposal from before the threshold change')
+    } catch (err) {
+      expect(String(err)).to.include('StaleTransaction')
+    }
+  })
+
+  it('charges the executor for owner growth and refunds it on shrink', async () => {
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: secondOwner.publicKey,
+          lamports: LAMPORTS_PER_SOL / 10,
+        })
+      )
+    )
+    const owner = Keypair.generate().publicKey
+
+    // The executor doesn't pay the fees, so its balance moves by rent alone.
+    const applyAndMeasure = async (action: any) => {
+      const executorBefore = await provider.connection.getBalance(secondOwner.publicKey)
+      const walletBefore = await provider.connection.getBalance(wallet)
+      await applyConfig(action, wallet, secondOwner)
+      const info = await provider.connection.getAccountInfo(wallet)
+      expect(info!.lamports).to.equal(
+        await provider.connection.getMinimumBalanceForRentExemption(info!.data.length)
+      )
+      const executorAfter = await provider.connection.getBalance(secondOwner.publicKey)
+      expect(executorAfter - executorBefore).to.equal(walletBefore - info!.lamports)
+      return info!.lamports - walletBefore
+    }
+
+    expect(await applyAndMeasure({ addOwner: { owner, permissions: 2, weight: 1 } })).to.be.above(0)
+    expect(await applyAndMeasure({ removeOwner: { owner } })).to.be.below(0)
+  })
+
+  it('lets every voting owner override the time lock', async () => {
+    const thirdOwner = Keypair.generate()
+    const operator = Keypair.generate()
+    const recipient = Keypair.generate().publicKey
+    const [lockedWallet, lockedBump] = predictWalletAddress(
+      provider.wallet.publicKey,
+      3,
+      program.programId
+    )
+    const [lockedVault] = findVaultAddress(lockedWallet, program.programId)
+    const solPdaFor = (seed: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(seed), lockedWallet.toBuffer(), PublicKey.default.toBuffer()],
+        program.programId
+      )[0]
+    const entry = findAddressBookEntryAddress(lockedWallet, recipient, program.programId)[0]
+
+    // The operator can't vote, so the override must not wait for it.
+    await program.methods
+      .initializeWallet(
+        [
+          { key: provider.wallet.publicKey, permissions: 7, weight: 1 },
+          { key: secondOwner.publicKey, permissions: 2, weight: 1 },
+          { key: thirdOwner.publicKey, permissions: 2, weight: 1 },
+          { key: operator.publicKey, permissions: 5, weight: 1 },
+        ],
+        2,
+        new BN(3_600),
+        lockedBump,
+        new BN(3)
+      )
+      .accounts({
+        wallet: lockedWallet,
+        authority: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: lockedVault,
+          lamports: LAMPORTS_PER_SOL / 10,
+        })
+      )
+    )
+
+    const [transaction] = findTransactionAddress(lockedWallet, 0, program.programId)
+    await program.methods
+      .createTransaction(recipient, new BN(LAMPORTS_PER_SOL / 100), null, null, null)
+      .accounts({
+        wallet: lockedWallet,
+        transaction,
+        spendingLimit: solPdaFor('spending_limit'),
+        amountPolicy: solPdaFor('amount_policy'),
+        addressBookEntry: entry,
+        proposer: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc()
+
+    const confirmBy = (owner: Keypair) =>
+      program.methods
+        .confirmTransaction()
+        .accounts({ wallet: lockedWallet, transaction, owner: owner.publicKey })
+        .signers([owner])
+        .rpc()
+    const execute = () =>
+      program.methods
+        .executeTransaction()
+        .accounts({
+          wallet: lockedWallet,
+          transaction,
+          vault: lockedVault,
+          spendingLimit: solPdaFor('spending_limit'),
+          addressBookEntry: entry,
+          destination: recipient,
+          vaultTokenAccount: null,
+          destinationTokenAccount: null,
+          mint: null,
+          executor: provider.wallet.publicKey,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: 'confirmed' })
+
+    await confirmBy(secondOwner)
+    try {
+      await execute()
+      expect.fail('executed inside the time lock with only the threshold')
+    } catch (err) {
+      expect(String(err)).to.include('TimeLockNotExpired')
+    }
+
+    await confirmBy(thirdOwner)
+    const signature = await execute()
+    expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL / 100)
+
+    const executed = await provider.connection.getTransaction(signature, {
+      commitment: 'confirmed',
+      maxSupportedTransactionVersion: 0,
+    })
+    const parser = new anchor.EventParser(program.programId, program.coder)
+    const events = [...parser.parseLogs(executed!.meta!.logMessages!)]
+    expect(events.map((event) => event.name)).to.include('TimeLockOverridden')
+  })
+
+  describe('weighted wallet', () => {
+    const PROPOSE = 1
+    const VOTE = 2
+    const EXECUTE = 4
+
+    const proposerOnly = Keypair.generate()
+    const executorOnly = Keypair.generate()
+    const [weightedWallet, weightedBump] = predictWalletAddress(
+      provider.wallet.publicKey,
+      1,
+      program.programId
+    )
+    const [weightedVault] = findVaultAddress(weightedWallet, program.programId)
+
+    // SOL spending limit or amount policy PDA of the weighted wallet.
+    const solPdaFor = (seed: string) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(seed), weightedWallet.toBuffer(), PublicKey.default.toBuffer()],
+        program.programId
+      )[0]
+
+    const proposeFrom = async (proposer: Keypair, destination: PublicKey, amount: number) => {
+      const { nonce } = await program.account.wallet.fetch(weightedWallet)
+      const [transaction] = findTransactionAddress(weightedWallet, nonce, program.programId)
+      await program.methods
+        .createTransaction(destination, new BN(amount), null, null, null)
+        .accounts({
+          wallet: weightedWallet,
+          transaction,
+          spendingLimit: solPdaFor('spending_limit'),
+          amountPolicy: solPdaFor('amount_policy'),
+          addressBookEntry: findAddressBookEntryAddress(
+            weightedWallet,
+            destination,
+            program.programId
+          )[0],
+          proposer: proposer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([proposer])
+        .rpc()
+      return transaction
+    }
+
+    const vote = (
+      method: 'confirmTransaction' | 'rejectTransaction',
+      transaction: PublicKey,
+      owner: Keypair
+    ) =>
+      program.methods[method]()
+        .accounts({ wallet: weightedWallet, transaction, owner: owner.publicKey })
+        .signers([owner])
+        .rpc()
+
+    const executeFrom = (executor: Keypair, transaction: PublicKey, destination: PublicKey) =>
+      program.methods
+        .executeTransaction()
+        .accounts({
+          wallet: weightedWallet,
+          transaction,
+          vault: weightedVault,
+          spendingLimit: solPdaFor('spending_limit'),
+          addressBookEntry: findAddressBookEntryAddress(
+            weightedWallet,
+            destination,
+            program.programId
+          )[0],
+          destination,
+          vaultTokenAccount: null,
+          destinationTokenAccount: null,
+          mint: null,
+          executor: executor.publicKey,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([executor])
+        .rpc()
+
+    before(async () => {
+      // The payer alone outweighs the plain voter; the other two keys can't vote.
+      await program.methods
+        .initializeWallet(
+          [
+            { key: provider.wallet.publicKey, permissions: PROPOSE | VOTE | EXECUTE, weight: 2 },
+            { key: secondOwner.publicKey, permissions: VOTE, weight: 1 },
+            { key: proposerOnly.publicKey, permissions: PROPOSE, weight: 1 },
+            { key: executorOnly.publicKey, permissions: EXECUTE, weight: 1 },
+          ],
+          3,
+          new BN(0),
+          weightedBump,
+          new BN(1)
+        )
+        .accounts({
+          wallet: weightedWallet,
+          authority: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc()
+
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          ...[
+            weightedVault,
+            secondOwner.publicKey,
+            proposerOnly.publicKey,
+            executorOnly.publicKey,
+          ].map((toPubkey) =>
+            SystemProgram.transfer({
+              fromPubkey: provider.wallet.publicKey,
+              toPubkey,
+              lamports: LAMPORTS_PER_SOL / 10,
+            })
+          )
+        )
+      )
+    })
+
+    it('rejects a threshold above the combined voting weight', async () => {
+      const [tooHigh, tooHighBump] = predictWalletAddress(
+        provider.wallet.publicKey,
+        2,
+        program.programId
+      )
+
+      try {
+        await program.methods
+          .initializeWallet(
+            [
+              { key: provider.wallet.publicKey, permissions: PROPOSE | VOTE | EXECUTE, weight: 2 },
+              { key: secondOwner.publicKey, permissions: VOTE, weight: 1 },
+            ],
+            4,
+            new BN(0),
+            tooHighBump,
+            new BN(2)
+          )
+          .accounts({
+            wallet: tooHigh,
+            authority: provider.wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc()
+        expect.fail('initialized an unreachable threshold')
+      } catch (err) {
+        expect(String(err)).to.include('InvalidThreshold')
+      }
+    })
+
+    it('executes once the confirmed weight reaches the threshold', async () => {
+      const recipient = Keypair.generate().publicKey
+      const transaction = await proposeFrom(proposerOnly, recipient, LAMPORTS_PER_SOL / 100)
+
+      await vote('confirmTransaction', transaction, payer)
+      try {
+        await executeFrom(payer, transaction, recipient)
+        expect.fail('executed below the threshold weight')
+      } catch (err) {
+        expect(String(err)).to.include('InsufficientConfirmations')
+      }
+
+      await vote('confirmTransaction', transaction, secondOwner)
+      await executeFrom(payer, transaction, recipient)
+
+      expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL / 100)
+    })
+
+    it('cancels once the rejected weight makes the threshold unreachable', async () => {
+      const transaction = await proposeFrom(
+        proposerOnly,
+        Keypair.generate().publicKey,
+        LAMPORTS_PER_SOL / 100
+      )
+
+      await vote('rejectTransaction', transaction, payer)
+
+      const account = await program.account.transaction.fetch(transaction)
+      expect(account.isCancelled).to.be.true
+    })
+
+    it('keeps proposing, voting and executing rights apart', async () => {
+      const recipient = Keypair.generate().publicKey
+      const transaction = await proposeFrom(proposerOnly, recipient, LAMPORTS_PER_SOL / 100)
+
+      for (const [owner, method] of [
+        [proposerOnly, 'confirmTransaction'],
+        [executorOnly, 'confirmTransaction'],
+        [executorOnly, 'rejectTransaction'],
+      ] as const) {
+        try {
+          await vote(method, transaction, owner)
+          expect.fail('voted without voting rights')
+        } catch (err) {
+          expect(String(err)).to.include('MissingPermission')
+        }
+      }
+
+      try {
+        await proposeFrom(secondOwner, recipient, 1)
+        expect.fail('proposed without proposing rights')
+      } catch (err) {
+        expect(String(err)).to.include('MissingPermission')
+      }
+
+      await vote('confirmTransaction', transaction, payer)
+      await vote('confirmTransaction', transaction, secondOwner)
+      try {
+        await executeFrom(proposerOnly, transaction, recipient)
+        expect.fail('executed without executing rights')
+      } catch (err) {
+        expect(String(err)).to.include('MissingPermission')
+      }
+      await executeFrom(executorOnly, transaction, recipient)
+
+      expect(await provider.connection.getBalance(recipient)).to.equal(LAMPORTS_PER_SOL / 100)
+    })
+  })
+})